@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use rawtx_rs::bitcoin::Txid;
+use serde::Serialize;
+
+use crate::blocks::MinedLookup;
+use crate::html;
+
+// Time windows of the report as (name, length in seconds). A length of 0
+// covers all replacements.
+const WINDOWS: [(&str, u64); 4] = [
+    ("last 24 hours", 24 * 60 * 60),
+    ("last 7 days", 7 * 24 * 60 * 60),
+    ("last 30 days", 30 * 24 * 60 * 60),
+    ("all", 0),
+];
+// Pool name used for blocks whose pool isn't known.
+const UNKNOWN_POOL: &str = "unknown";
+
+// The txids of all full-RBF replacement events, so that the report covers
+// all of them and not only the rendered ones.
+#[derive(Default)]
+pub struct AdoptionEvents {
+    // Timestamp, replaced and replacement txid.
+    events: Vec<(u64, Txid, Txid)>,
+}
+
+impl AdoptionEvents {
+    pub fn insert(&mut self, replacement: &html::ReplacementContext) {
+        if let (Ok(replaced), Ok(new)) = (
+            Txid::from_str(&replacement.replaced.txid),
+            Txid::from_str(&replacement.replacement.txid),
+        ) {
+            self.events.push((replacement.timestamp, replaced, new));
+        }
+    }
+
+    pub fn txids(&self) -> HashSet<Txid> {
+        self.events
+            .iter()
+            .flat_map(|(_, replaced, replacement)| [*replaced, *replacement])
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct PoolCounts {
+    replacement_mined: u64,
+    replaced_mined: u64,
+}
+
+// A row of the CSV report.
+#[derive(Serialize)]
+pub struct ReportRow<'a> {
+    window: &'a str,
+    pool: &'a str,
+    replacement_mined: u64,
+    replaced_mined: u64,
+    share: &'a str,
+}
+
+fn pool_name(mined: &html::MinedContext) -> &str {
+    mined.pool.as_deref().unwrap_or(UNKNOWN_POOL)
+}
+
+// Counts per replacement group, i.e. per timestamp and replacement, if a pool
+// mined the replacement or one of the replaced transactions. A pool mining
+// multiple replaced transactions of a group counts once, so that both counts
+// are comparable. The events must be sorted.
+fn count_pools(events: &[(u64, Txid, Txid)], mined: &MinedLookup) -> Vec<(String, PoolCounts)> {
+    let mut pools: HashMap<String, PoolCounts> = HashMap::new();
+    for group in events.chunk_by(|a, b| (a.0, a.2) == (b.0, b.2)) {
+        let (_, _, replacement) = group[0];
+        if let Some(m) = mined.tx(&replacement) {
+            pools
+                .entry(pool_name(m).to_string())
+                .or_default()
+                .replacement_mined += 1;
+        }
+        let replaced_pools: HashSet<&str> = group
+            .iter()
+            .filter_map(|(_, replaced, _)| mined.tx(replaced))
+            .map(pool_name)
+            .collect();
+        for pool in replaced_pools {
+            pools.entry(pool.to_string()).or_default().replaced_mined += 1;
+        }
+    }
+    let mut pools: Vec<(String, PoolCounts)> = pools.into_iter().collect();
+    pools.sort_by(|(a_name, a), (b_name, b)| {
+        b.replacement_mined
+            .cmp(&a.replacement_mined)
+            .then(a.replaced_mined.cmp(&b.replaced_mined))
+            .then(a_name.cmp(b_name))
+    });
+    pools
+}
+
+// Ranks the pools by the number of full-RBF replacements they mined in each
+// time window, compared to the number of replaced transactions they mined
+// instead.
+pub fn build_report(
+    events: AdoptionEvents,
+    mined: &MinedLookup,
+    timestamp: u64,
+) -> html::AdoptionSiteContext {
+    let mut events = events.events;
+    events.sort_by_key(|(timestamp, _, replacement)| (*timestamp, *replacement));
+    let windows = WINDOWS
+        .iter()
+        .map(|(name, length)| {
+            // The events are sorted by timestamp, so the window is a suffix.
+            let start = events.partition_point(|(t, _, _)| *length != 0 && t + length < timestamp);
+            let events = &events[start..];
+            let mut groups: Vec<(u64, Txid)> = events.iter().map(|(t, _, r)| (*t, *r)).collect();
+            groups.dedup();
+            html::AdoptionWindowContext {
+                name: name.to_string(),
+                replacements: groups.len(),
+                pools: count_pools(events, mined)
+                    .into_iter()
+                    .map(|(name, counts)| html::PoolAdoptionContext {
+                        share: format!(
+                            "{:.1}",
+                            counts.replacement_mined as f64 * 100.0
+                                / (counts.replacement_mined + counts.replaced_mined) as f64
+                        ),
+                        name,
+                        replacement_mined: counts.replacement_mined,
+                        replaced_mined: counts.replaced_mined,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    html::AdoptionSiteContext { timestamp, windows }
+}
+
+pub fn csv_rows(report: &html::AdoptionSiteContext) -> Vec<ReportRow<'_>> {
+    report
+        .windows
+        .iter()
+        .flat_map(|window| {
+            window.pools.iter().map(|pool| ReportRow {
+                window: &window.name,
+                pool: &pool.name,
+                replacement_mined: pool.replacement_mined,
+                replaced_mined: pool.replaced_mined,
+                share: &pool.share,
+            })
+        })
+        .collect()
+}
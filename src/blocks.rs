@@ -0,0 +1,531 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use rawtx_rs::bitcoin::{self, OutPoint, Txid};
+
+use crate::error::Error;
+use crate::html;
+use crate::pools::Pools;
+
+// What happened to the transactions of a replacement group, as far as known
+// from the local blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    // The replacement was mined.
+    Replacement,
+    // One of the replaced transactions was mined.
+    Replaced,
+    // Another transaction spending an input of the group was mined.
+    Conflict,
+    // None of the transactions was mined yet.
+    Pending,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 4] = [
+        Outcome::Replacement,
+        Outcome::Replaced,
+        Outcome::Conflict,
+        Outcome::Pending,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Replacement => "replacement-mined",
+            Outcome::Replaced => "replaced-mined",
+            Outcome::Conflict => "conflict-mined",
+            Outcome::Pending => "not-mined",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Outcome::Replacement => "replacement mined",
+            Outcome::Replaced => "replaced transaction mined",
+            Outcome::Conflict => "conflicting transaction mined",
+            Outcome::Pending => "not mined yet",
+        }
+    }
+}
+
+// The transactions and spends found in the local blocks.
+#[derive(Default)]
+pub struct MinedLookup {
+    txs: HashMap<Txid, html::MinedContext>,
+    // Outpoints spent by the mined transactions, with the spending txid and
+    // its block. A later replacement in a chain is a conflict too.
+    spends: HashMap<OutPoint, (Txid, html::MinedContext)>,
+}
+
+impl MinedLookup {
+    // Returns the block the transaction was mined in, if found.
+    pub fn tx(&self, txid: &Txid) -> Option<&html::MinedContext> {
+        self.txs.get(txid)
+    }
+}
+
+#[cfg(test)]
+impl MinedLookup {
+    pub fn insert_tx(&mut self, txid: Txid, mined: html::MinedContext) {
+        self.txs.insert(txid, mined);
+    }
+}
+
+// Bitcoin Core obfuscates the blk*.dat files with the key in this file since
+// v28.0.
+const XOR_KEY_FILE: &str = "xor.dat";
+
+// Extensions of files with a single raw block. Other files, e.g. the rev*.dat
+// undo files in a Bitcoin Core blocks directory, are skipped.
+const RAW_BLOCK_EXTENSIONS: [&str; 3] = [".hex", ".bin", ".block"];
+
+fn is_blk_file(name: &str) -> bool {
+    name.starts_with("blk") && name.ends_with(".dat")
+}
+
+fn is_raw_block_file(name: &str) -> bool {
+    RAW_BLOCK_EXTENSIONS.iter().any(|e| name.ends_with(e))
+}
+
+// A block containing one of the looked up transactions or spends. It's only
+// counted once it's known to not be stale.
+struct FoundBlock {
+    hash: bitcoin::BlockHash,
+    context: html::MinedContext,
+    txs: Vec<Txid>,
+    spends: Vec<(OutPoint, Txid)>,
+}
+
+// Follows the blocks from the highest one back to find the block of the active
+// chain at each height. Of competing blocks at the highest height, the one
+// read first is used, as Bitcoin Core stays on the first block it received.
+fn active_chain(
+    headers: &HashMap<bitcoin::BlockHash, (u64, bitcoin::BlockHash)>,
+    tip: Option<bitcoin::BlockHash>,
+) -> HashMap<u64, bitcoin::BlockHash> {
+    let mut active: HashMap<u64, bitcoin::BlockHash> = HashMap::new();
+    let mut current = tip;
+    while let Some(hash) = current {
+        let Some((height, prev)) = headers.get(&hash) else {
+            break;
+        };
+        active.insert(*height, hash);
+        current = Some(*prev);
+    }
+    active
+}
+
+fn block_error(path: &Path, reason: String) -> Error {
+    Error::Block {
+        path: path.display().to_string(),
+        reason,
+    }
+}
+
+fn decode_block(path: &Path, bytes: &[u8]) -> Result<bitcoin::Block, Error> {
+    bitcoin::consensus::encode::deserialize(bytes)
+        .map_err(|e| block_error(path, format!("invalid block: {}", e)))
+}
+
+// Reads a file containing a single block, either binary or hex encoded as
+// returned by `getblock <hash> 0`.
+fn read_raw_block(path: &Path, bytes: &[u8]) -> Result<Vec<bitcoin::Block>, Error> {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim();
+    if !text.is_empty() && text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        let bytes =
+            hex::decode(text).map_err(|e| block_error(path, format!("invalid hex: {}", e)))?;
+        return Ok(vec![decode_block(path, &bytes)?]);
+    }
+    Ok(vec![decode_block(path, bytes)?])
+}
+
+// Reads the blocks from a Bitcoin Core blk*.dat file. Each block is prefixed
+// with the network magic and its size. The unused end of the file is zeroed.
+fn read_blk_file(path: &Path, bytes: &[u8]) -> Result<Vec<bitcoin::Block>, Error> {
+    let mut blocks: Vec<bitcoin::Block> = Vec::new();
+    let mut offset = 0;
+    while offset + 8 <= bytes.len() {
+        if bytes[offset..offset + 4] == [0; 4] {
+            break;
+        }
+        let mut size = [0; 4];
+        size.copy_from_slice(&bytes[offset + 4..offset + 8]);
+        let start = offset + 8;
+        let end = start + u32::from_le_bytes(size) as usize;
+        if end > bytes.len() {
+            return Err(block_error(
+                path,
+                format!("truncated block at offset {}", offset),
+            ));
+        }
+        blocks.push(decode_block(path, &bytes[start..end])?);
+        offset = end;
+    }
+    Ok(blocks)
+}
+
+// Obfuscating and deobfuscating are the same XOR with the repeated key.
+fn xor(bytes: &mut [u8], key: &[u8]) {
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b ^= key[i % key.len()];
+    }
+}
+
+fn read_xor_key(dir: &Path) -> Result<Option<Vec<u8>>, Error> {
+    let path = dir.join(XOR_KEY_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let key = fs::read(&path).map_err(|e| Error::Io {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    // An all-zero key means the files aren't obfuscated.
+    Ok(Some(key).filter(|k| k.iter().any(|b| *b != 0)))
+}
+
+// Searches the blocks in `dir` for the transactions and for other
+// transactions spending the same outpoints. Returns the block each of the
+// found transactions was mined in and the pool that mined it. The directory
+// can contain Bitcoin Core blk*.dat files or files with a single raw block
+// each. Blocks that aren't part of the active chain, as far as it can be
+// followed through the blocks in the directory, are ignored.
+pub fn find_mined(
+    dir: &str,
+    txids: &HashSet<Txid>,
+    outpoints: &HashSet<OutPoint>,
+    pools: &Pools,
+) -> Result<MinedLookup, Error> {
+    let dir_path = Path::new(dir);
+    let io_error = |e| Error::Io {
+        path: dir.to_string(),
+        source: e,
+    };
+    let xor_key = read_xor_key(dir_path)?;
+    let mut paths: Vec<_> = fs::read_dir(dir_path)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()
+        .map_err(io_error)?;
+    paths.retain(|p| {
+        let name = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        p.is_file() && (is_blk_file(name) || is_raw_block_file(name))
+    });
+    paths.sort();
+
+    let mut found_blocks: Vec<FoundBlock> = Vec::new();
+    // Block hash to height and previous block hash.
+    let mut headers: HashMap<bitcoin::BlockHash, (u64, bitcoin::BlockHash)> = HashMap::new();
+    let mut tip: Option<(u64, bitcoin::BlockHash)> = None;
+    let mut block_count: u64 = 0;
+    for path in paths {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let is_blk = is_blk_file(name);
+        let mut bytes = fs::read(&path).map_err(|e| Error::Io {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        let blocks = if is_blk {
+            if let Some(key) = &xor_key {
+                xor(&mut bytes, key);
+            }
+            read_blk_file(&path, &bytes)?
+        } else {
+            read_raw_block(&path, &bytes)?
+        };
+
+        for block in blocks {
+            block_count += 1;
+            // Blocks without a BIP-34 height, i.e. before height 227931,
+            // can't contain any of the logged transactions.
+            let Ok(height) = block.bip34_block_height() else {
+                continue;
+            };
+            let hash = block.block_hash();
+            headers.insert(hash, (height, block.header.prev_blockhash));
+            if tip.is_none_or(|(tip_height, _)| height > tip_height) {
+                tip = Some((height, hash));
+            }
+            let mut found: Vec<Txid> = Vec::new();
+            let mut spends: Vec<(OutPoint, Txid)> = Vec::new();
+            for tx in &block.txdata {
+                let txid = tx.txid();
+                if txids.contains(&txid) {
+                    found.push(txid);
+                }
+                for input in &tx.input {
+                    if outpoints.contains(&input.previous_output) {
+                        spends.push((input.previous_output, txid));
+                    }
+                }
+            }
+            if found.is_empty() && spends.is_empty() {
+                continue;
+            }
+            found_blocks.push(FoundBlock {
+                hash,
+                context: html::MinedContext {
+                    height,
+                    hash: hash.to_string(),
+                    pool: pools.identify(&block),
+                },
+                txs: found,
+                spends,
+            });
+        }
+    }
+
+    // A block is stale if the active chain has another block at its height.
+    // Blocks at heights the chain can't be followed to are kept.
+    let active = active_chain(&headers, tip.map(|(_, hash)| hash));
+    let mut mined = MinedLookup::default();
+    let mut stale: u64 = 0;
+    for block in found_blocks {
+        if active
+            .get(&block.context.height)
+            .is_some_and(|hash| *hash != block.hash)
+        {
+            stale += 1;
+            continue;
+        }
+        for txid in block.txs {
+            mined
+                .txs
+                .entry(txid)
+                .or_insert_with(|| block.context.clone());
+        }
+        for (outpoint, txid) in block.spends {
+            mined
+                .spends
+                .entry(outpoint)
+                .or_insert_with(|| (txid, block.context.clone()));
+        }
+    }
+    if stale > 0 {
+        info!("Ignored {} stale blocks", stale);
+    }
+    info!(
+        "Found {} of {} transactions and {} spent outpoints in {} blocks from {}",
+        mined.txs.len(),
+        txids.len(),
+        mined.spends.len(),
+        block_count,
+        dir
+    );
+    Ok(mined)
+}
+
+fn group_txids(group: &html::ReplacementGroupContext) -> impl Iterator<Item = &str> {
+    group
+        .replaced
+        .iter()
+        .chain([&group.replacement])
+        .map(|tx| tx.txid.as_str())
+}
+
+// Returns the txids of all transactions in the groups.
+pub fn txids(groups: &[html::ReplacementGroupContext]) -> HashSet<Txid> {
+    groups
+        .iter()
+        .flat_map(group_txids)
+        .filter_map(|txid| Txid::from_str(txid).ok())
+        .collect()
+}
+
+// Returns the outpoints spent by the transaction.
+fn tx_outpoints(tx: &html::TransactionContext) -> Vec<OutPoint> {
+    hex::decode(&tx.raw)
+        .ok()
+        .and_then(|raw| bitcoin::consensus::encode::deserialize::<bitcoin::Transaction>(&raw).ok())
+        .map(|tx| tx.input.into_iter().map(|i| i.previous_output).collect())
+        .unwrap_or_default()
+}
+
+// Returns the outpoints spent by the transactions of the group.
+fn group_outpoints(group: &html::ReplacementGroupContext) -> Vec<OutPoint> {
+    group
+        .replaced
+        .iter()
+        .chain([&group.replacement])
+        .flat_map(tx_outpoints)
+        .collect()
+}
+
+// Returns the outpoints spent by the transactions of the replacement event.
+pub fn event_outpoints(replacement: &html::ReplacementContext) -> Vec<OutPoint> {
+    let mut outpoints = tx_outpoints(&replacement.replaced);
+    outpoints.extend(tx_outpoints(&replacement.replacement));
+    outpoints
+}
+
+// Returns the outpoints spent by all transactions in the groups.
+pub fn outpoints(groups: &[html::ReplacementGroupContext]) -> HashSet<OutPoint> {
+    groups.iter().flat_map(group_outpoints).collect()
+}
+
+fn mined_label(mined: &html::MinedContext) -> String {
+    match &mined.pool {
+        Some(pool) => format!("in {} by {}", mined.height, pool),
+        None => format!("in {}", mined.height),
+    }
+}
+
+fn outcome(group: &html::ReplacementGroupContext, mined: &MinedLookup) -> html::OutcomeContext {
+    let (outcome, label) = if let Some(m) = &group.replacement.mined {
+        (
+            Outcome::Replacement,
+            format!("replacement mined {}", mined_label(m)),
+        )
+    } else if let Some(m) = group.replaced.iter().find_map(|tx| tx.mined.as_ref()) {
+        (
+            Outcome::Replaced,
+            format!("replaced transaction mined {}", mined_label(m)),
+        )
+    } else if let Some((txid, m)) = group_outpoints(group)
+        .iter()
+        .filter_map(|outpoint| mined.spends.get(outpoint))
+        .find(|(txid, _)| !group_txids(group).any(|t| t == txid.to_string()))
+    {
+        (
+            Outcome::Conflict,
+            format!("conflicting transaction {} mined {}", txid, mined_label(m)),
+        )
+    } else {
+        (Outcome::Pending, Outcome::Pending.title().to_string())
+    };
+    html::OutcomeContext {
+        name: outcome.name().to_string(),
+        label,
+    }
+}
+
+// Sets the mined status of the transactions in the groups and the outcome of
+// the groups.
+pub fn set_mined(groups: &mut [html::ReplacementGroupContext], mined: &MinedLookup) {
+    for group in groups {
+        for tx in group.replaced.iter_mut().chain([&mut group.replacement]) {
+            tx.mined_checked = true;
+            tx.mined = Txid::from_str(&tx.txid)
+                .ok()
+                .and_then(|txid| mined.txs.get(&txid))
+                .cloned();
+        }
+        group.outcome = Some(outcome(group, mined));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rawtx_rs::bitcoin::blockdata::block::BlockHeader;
+    use rawtx_rs::bitcoin::consensus::encode::serialize;
+    use rawtx_rs::bitcoin::hashes::Hash;
+    use rawtx_rs::bitcoin::{PackedLockTime, Script, Sequence, TxIn, TxOut, Witness};
+
+    const MAGIC: [u8; 4] = [0xf9, 0xbe, 0xb4, 0xd9];
+
+    // A block with only a coinbase transaction committing to the height.
+    fn block(height: u32, prev: bitcoin::BlockHash) -> bitcoin::Block {
+        let height = height.to_le_bytes();
+        let coinbase = bitcoin::Transaction {
+            version: 1,
+            lock_time: PackedLockTime(0),
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Script::from(vec![3, height[0], height[1], height[2]]),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: 625_000_000,
+                script_pubkey: Script::new(),
+            }],
+        };
+        bitcoin::Block {
+            header: BlockHeader {
+                version: 0x20000000,
+                prev_blockhash: prev,
+                merkle_root: bitcoin::TxMerkleNode::from_inner(coinbase.txid().into_inner()),
+                time: 1670000000,
+                bits: 0,
+                nonce: 0,
+            },
+            txdata: vec![coinbase],
+        }
+    }
+
+    // Writes the blocks as Bitcoin Core does, followed by the zeroed unused
+    // end of the file.
+    fn blk_file(blocks: &[bitcoin::Block], padding: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for block in blocks {
+            let raw = serialize(block);
+            bytes.extend(MAGIC);
+            bytes.extend((raw.len() as u32).to_le_bytes());
+            bytes.extend(raw);
+        }
+        bytes.extend(vec![0; padding]);
+        bytes
+    }
+
+    fn two_blocks() -> Vec<bitcoin::Block> {
+        let first = block(800000, bitcoin::BlockHash::all_zeros());
+        let second = block(800001, first.block_hash());
+        vec![first, second]
+    }
+
+    fn hashes(blocks: &[bitcoin::Block]) -> Vec<bitcoin::BlockHash> {
+        blocks.iter().map(|b| b.block_hash()).collect()
+    }
+
+    #[test]
+    fn read_blk_file_stops_at_padding() {
+        let blocks = two_blocks();
+        let path = Path::new("blk00000.dat");
+        for padding in [0, 4, 7, 8, 1000] {
+            let read = read_blk_file(path, &blk_file(&blocks, padding)).unwrap();
+            assert_eq!(hashes(&read), hashes(&blocks));
+        }
+        assert!(read_blk_file(path, &[0; 100]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_blk_file_with_xor_key() {
+        let blocks = two_blocks();
+        let path = Path::new("blk00000.dat");
+        let key = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut bytes = blk_file(&blocks, 100);
+        xor(&mut bytes, &key);
+        assert_ne!(bytes[..4], MAGIC);
+        // Reading the obfuscated file fails on the obfuscated block size.
+        assert!(read_blk_file(path, &bytes).is_err());
+
+        xor(&mut bytes, &key);
+        let read = read_blk_file(path, &bytes).unwrap();
+        assert_eq!(hashes(&read), hashes(&blocks));
+        assert_eq!(read[1].bip34_block_height().unwrap(), 800001);
+    }
+
+    #[test]
+    fn read_blk_file_rejects_truncated_block() {
+        let blocks = two_blocks();
+        let bytes = blk_file(&blocks, 0);
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(read_blk_file(Path::new("blk00000.dat"), truncated).is_err());
+    }
+
+    #[test]
+    fn block_file_names() {
+        assert!(is_blk_file("blk00000.dat"));
+        assert!(!is_blk_file("rev00000.dat"));
+        assert!(!is_blk_file("xor.dat"));
+        assert!(is_raw_block_file("800000.hex"));
+        assert!(!is_raw_block_file("rev00000.dat"));
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+use rawtx_rs::bitcoin::Txid;
+
+use crate::html;
+
+struct Edge {
+    timestamp: u64,
+    replacement: Txid,
+}
+
+// A graph of all replacements used to follow transactions that are replaced
+// more than once. Only the txids, fees and vsizes of the transactions are kept
+// in memory, with the txids as 32 bytes instead of hex strings.
+#[derive(Default)]
+pub struct ReplacementGraph {
+    // Replaced txid to the transaction it was replaced by.
+    replaced_by: HashMap<Txid, Edge>,
+    // Replacement txid to the transactions it replaced.
+    replaces: HashMap<Txid, Vec<Txid>>,
+    // Txid to fee and vsize.
+    txs: HashMap<Txid, (u64, u64)>,
+}
+
+impl ReplacementGraph {
+    pub fn insert(&mut self, replacement: &html::ReplacementContext) {
+        let (Ok(replaced), Ok(replacement_txid)) = (
+            Txid::from_str(&replacement.replaced.txid),
+            Txid::from_str(&replacement.replacement.txid),
+        ) else {
+            return;
+        };
+        for (txid, tx) in [
+            (replaced, &replacement.replaced),
+            (replacement_txid, &replacement.replacement),
+        ] {
+            self.txs.entry(txid).or_insert((tx.fee, tx.vsize));
+        }
+        // A transaction can only be replaced once. Later events for it are
+        // duplicates.
+        if self.replaced_by.contains_key(&replaced) {
+            return;
+        }
+        self.replaced_by.insert(
+            replaced,
+            Edge {
+                timestamp: replacement.timestamp,
+                replacement: replacement_txid,
+            },
+        );
+        self.replaces
+            .entry(replacement_txid)
+            .or_default()
+            .push(replaced);
+    }
+
+    // Follows the replacements of a transaction to the last one.
+    fn tip(&self, txid: Txid) -> Txid {
+        let mut tip = txid;
+        let mut seen: HashSet<Txid> = HashSet::new();
+        while let Some(edge) = self.replaced_by.get(&tip) {
+            if !seen.insert(tip) {
+                break;
+            }
+            tip = edge.replacement;
+        }
+        tip
+    }
+
+    // Returns the versions of a transaction from the first one to the last
+    // replacement. When a replacement replaced multiple transactions, the
+    // chain continues with a transaction that is a replacement itself.
+    fn versions(&self, txid: Txid) -> Vec<Txid> {
+        let mut current = self.tip(txid);
+        let mut versions = vec![current];
+        let mut seen: HashSet<Txid> = HashSet::from([current]);
+        while let Some(replaced) = self.replaces.get(&current) {
+            let previous = *replaced
+                .iter()
+                .find(|t| self.replaces.contains_key(*t))
+                .unwrap_or(&replaced[0]);
+            if !seen.insert(previous) {
+                break;
+            }
+            versions.push(previous);
+            current = previous;
+        }
+        versions.reverse();
+        versions
+    }
+
+    // Returns the chain the transaction is part of, if the chain has at least
+    // two replacements.
+    pub fn chain(&self, txid: &str) -> Option<html::ChainContext> {
+        let versions = self.versions(Txid::from_str(txid).ok()?);
+        if versions.len() < 3 {
+            return None;
+        }
+        let first_timestamp = self.replaced_by.get(&versions[0])?.timestamp;
+        let mut steps: Vec<html::ChainStepContext> = Vec::new();
+        let mut previous_fee: Option<u64> = None;
+        for (i, txid) in versions.iter().enumerate() {
+            let (fee, vsize) = self.txs.get(txid).copied().unwrap_or_default();
+            let timestamp = match i {
+                0 => first_timestamp,
+                _ => self.replaced_by.get(&versions[i - 1])?.timestamp,
+            };
+            steps.push(html::ChainStepContext {
+                txid: txid.to_string(),
+                fee,
+                vsize,
+                feerate: format!("{:.2}", fee as f64 / vsize as f64),
+                fee_delta: fee as i64 - previous_fee.unwrap_or(fee) as i64,
+                seconds: timestamp.saturating_sub(first_timestamp),
+            });
+            previous_fee = Some(fee);
+        }
+        let first = steps.first()?;
+        let last = steps.last()?;
+        Some(html::ChainContext {
+            tip: last.txid.clone(),
+            replacements: steps.len() - 1,
+            fee_delta: last.fee as i64 - first.fee as i64,
+            duration: last.seconds,
+            timestamp: first_timestamp,
+            steps,
+        })
+    }
+
+    // Links the groups to the chains their replacements are part of and
+    // collects these chains by their tip.
+    pub fn link(
+        &self,
+        groups: &mut [html::ReplacementGroupContext],
+        chains: &mut BTreeMap<String, html::ChainContext>,
+    ) {
+        for group in groups {
+            if let Some(chain) = self.chain(&group.replacement.txid) {
+                group.chain = Some(html::ChainLinkContext {
+                    tip: chain.tip.clone(),
+                    replacements: chain.replacements,
+                });
+                chains.entry(chain.tip.clone()).or_insert(chain);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{replacement, txid};
+
+    fn insert(graph: &mut ReplacementGraph, timestamp: u64, replaced: u8, new: u8) {
+        graph.insert(&replacement(timestamp, replaced, new));
+    }
+
+    #[test]
+    fn versions_of_linear_chain() {
+        let mut graph = ReplacementGraph::default();
+        insert(&mut graph, 100, 1, 2);
+        insert(&mut graph, 160, 2, 3);
+        insert(&mut graph, 220, 3, 4);
+        let expected = vec![txid(1), txid(2), txid(3), txid(4)];
+        for n in 1..=4 {
+            assert_eq!(graph.versions(txid(n)), expected);
+        }
+        // Unknown transactions are a version of their own.
+        assert_eq!(graph.versions(txid(9)), vec![txid(9)]);
+    }
+
+    #[test]
+    fn chain_of_linear_chain() {
+        let mut graph = ReplacementGraph::default();
+        insert(&mut graph, 100, 1, 2);
+        insert(&mut graph, 160, 2, 3);
+        insert(&mut graph, 220, 3, 4);
+        let chain = graph.chain(&txid(2).to_string()).unwrap();
+        assert_eq!(chain.tip, txid(4).to_string());
+        assert_eq!(chain.replacements, 3);
+        assert_eq!(chain.fee_delta, 3000);
+        assert_eq!(chain.duration, 120);
+        assert_eq!(chain.timestamp, 100);
+        let seconds: Vec<u64> = chain.steps.iter().map(|s| s.seconds).collect();
+        assert_eq!(seconds, vec![0, 0, 60, 120]);
+        let fee_deltas: Vec<i64> = chain.steps.iter().map(|s| s.fee_delta).collect();
+        assert_eq!(fee_deltas, vec![0, 1000, 1000, 1000]);
+    }
+
+    #[test]
+    fn no_chain_for_single_replacement() {
+        let mut graph = ReplacementGraph::default();
+        insert(&mut graph, 100, 1, 2);
+        assert!(graph.chain(&txid(1).to_string()).is_none());
+        assert!(graph.chain(&txid(2).to_string()).is_none());
+        assert!(graph.chain("not a txid").is_none());
+    }
+
+    #[test]
+    fn versions_through_multi_replaced_group() {
+        let mut graph = ReplacementGraph::default();
+        // 3 replaces 2, which replaced 1, and the unrelated 8 at once.
+        insert(&mut graph, 100, 1, 2);
+        insert(&mut graph, 160, 8, 3);
+        insert(&mut graph, 160, 2, 3);
+        insert(&mut graph, 220, 3, 4);
+        let expected = vec![txid(1), txid(2), txid(3), txid(4)];
+        assert_eq!(graph.versions(txid(4)), expected);
+        assert_eq!(graph.versions(txid(1)), expected);
+        // The chain of the other replaced transaction continues with the
+        // replacements of 3 as well.
+        assert_eq!(graph.versions(txid(8)), expected);
+        let chain = graph.chain(&txid(8).to_string()).unwrap();
+        assert_eq!(chain.tip, txid(4).to_string());
+        assert_eq!(chain.replacements, 3);
+    }
+
+    #[test]
+    fn duplicate_and_cyclic_events() {
+        let mut graph = ReplacementGraph::default();
+        insert(&mut graph, 100, 1, 2);
+        // A transaction can only be replaced once.
+        insert(&mut graph, 100, 1, 5);
+        insert(&mut graph, 160, 2, 1);
+        assert_eq!(graph.tip(txid(1)), txid(1));
+        assert_eq!(graph.versions(txid(1)), vec![txid(2), txid(1)]);
+    }
+}
@@ -0,0 +1,259 @@
+use std::str::FromStr;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use rawtx_rs::bitcoin::Txid;
+
+use crate::input::InputFormat;
+use crate::rbf::ReplacementClass;
+use crate::{log, MAX_PAGES, REPLACEMENT_GROUPS_PER_PAGE, SITE_URL};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Renders the replacement events logged by a Bitcoin Core node into a static website"
+)]
+pub struct Cli {
+    #[arg(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        help = "Print more details about the progress"
+    )]
+    pub verbose: u8,
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Only print errors and the output of the command"
+    )]
+    pub quiet: bool,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+impl Cli {
+    pub fn verbosity(&self) -> i8 {
+        if self.quiet {
+            log::QUIET
+        } else {
+            log::INFO + self.verbose.min(log::DEBUG as u8) as i8
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Render the HTML pages, feeds and reports of the replacements")]
+    Render(RenderArgs),
+    #[command(about = "Print statistics over the full-RBF replacements")]
+    Stats(StatsArgs),
+    #[command(about = "Check the inputs for malformed replacement events without rendering")]
+    Validate(ValidateArgs),
+    #[command(about = "Convert replacement events between the CSV and JSONL formats")]
+    Convert(ConvertArgs),
+    #[command(about = "Print the replacements a transaction is part of as JSON lines")]
+    Query(QueryArgs),
+}
+
+fn parse_jobs(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("'{}' isn't a positive number", s)),
+        Ok(jobs) => Ok(jobs),
+    }
+}
+
+// Options for reading and analyzing the replacement events, shared by the
+// commands that decode the events.
+#[derive(Args)]
+pub struct Options {
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = InputFormat::from_str,
+        help = "Format of the inputs, csv or jsonl [default: detected from the file extension]"
+    )]
+    pub input_format: Option<InputFormat>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_jobs,
+        help = "Number of threads decoding the events [default: number of CPUs]"
+    )]
+    pub jobs: Option<usize>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "`getrawmempool true` snapshot used to detect transactions inheriting BIP-125 signaling from parents not seen in the events"
+    )]
+    pub mempool_snapshot: Option<String>,
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    #[command(flatten)]
+    pub options: Options,
+    #[arg(
+        long,
+        help = "Skip malformed replacement events instead of failing and write them to rejected.csv"
+    )]
+    pub lenient: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "SQLite store the events are imported into and rendered from"
+    )]
+    pub store: Option<String>,
+    #[arg(
+        long,
+        value_name = "CLASS,...",
+        value_delimiter = ',',
+        value_parser = ReplacementClass::from_str,
+        help = "Replacement classes, other than full-rbf, to render pages for: opt-in, descendant-eviction, inherited-signaling, truc"
+    )]
+    pub render_classes: Vec<ReplacementClass>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "View definitions of the filtered views to render [default: the bundled views.json]"
+    )]
+    pub views: Option<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = REPLACEMENT_GROUPS_PER_PAGE,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Number of replacements per page"
+    )]
+    pub page_size: u32,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = MAX_PAGES,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Maximum number of pages per view"
+    )]
+    pub max_pages: u32,
+    #[arg(
+        long,
+        value_name = "URL",
+        default_value = SITE_URL,
+        help = "URL the site is served from, used in the feeds"
+    )]
+    pub site_url: String,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Blocks to look up the mined status in, instead of loading it in the browser"
+    )]
+    pub blocks: Option<String>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Pool definitions to identify the pools that mined the blocks [default: the bundled pools.json]"
+    )]
+    pub pools: Option<String>,
+    #[arg(
+        required = true,
+        value_name = "INPUT",
+        help = "CSV or JSONL files, directories or glob patterns to read the events from, or - for stdin"
+    )]
+    pub inputs: Vec<String>,
+    #[arg(help = "Directory to write the HTML files to")]
+    pub output_dir: String,
+}
+
+#[derive(Args)]
+pub struct StatsArgs {
+    #[command(flatten)]
+    pub options: Options,
+    #[arg(long, help = "Skip malformed replacement events instead of failing")]
+    pub lenient: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "SQLite store the events are imported into and read from"
+    )]
+    pub store: Option<String>,
+    #[arg(
+        required_unless_present = "store",
+        value_name = "INPUT",
+        help = "CSV or JSONL files, directories or glob patterns to read the events from, or - for stdin"
+    )]
+    pub inputs: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    #[command(flatten)]
+    pub options: Options,
+    #[arg(
+        required = true,
+        value_name = "INPUT",
+        help = "CSV or JSONL files, directories or glob patterns to read the events from, or - for stdin"
+    )]
+    pub inputs: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = InputFormat::from_str,
+        help = "Format of the inputs, csv or jsonl [default: detected from the file extension]"
+    )]
+    pub input_format: Option<InputFormat>,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = InputFormat::from_str,
+        help = "Format to convert the events to, csv or jsonl"
+    )]
+    pub to: InputFormat,
+    #[arg(
+        short,
+        long,
+        value_name = "PATH",
+        default_value = "-",
+        help = "File to write the converted events to, or - for stdout"
+    )]
+    pub output: String,
+    #[arg(long, help = "Skip malformed replacement events instead of failing")]
+    pub lenient: bool,
+    #[arg(
+        required = true,
+        value_name = "INPUT",
+        help = "CSV or JSONL files, directories or glob patterns to read the events from, or - for stdin"
+    )]
+    pub inputs: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct QueryArgs {
+    #[command(flatten)]
+    pub options: Options,
+    #[arg(long, help = "Skip malformed replacement events instead of failing")]
+    pub lenient: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "SQLite store the events are imported into and read from"
+    )]
+    pub store: Option<String>,
+    #[arg(
+        long = "txid",
+        value_name = "TXID",
+        required = true,
+        value_parser = Txid::from_str,
+        help = "Transaction to look up, replaced or replacement. Can be given multiple times"
+    )]
+    pub txids: Vec<Txid>,
+    #[arg(
+        required_unless_present = "store",
+        value_name = "INPUT",
+        help = "CSV or JSONL files, directories or glob patterns to read the events from, or - for stdin"
+    )]
+    pub inputs: Vec<String>,
+}
@@ -0,0 +1,223 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use rawtx_rs::bitcoin;
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    Csv {
+        path: String,
+        source: csv::Error,
+    },
+    Row {
+        path: String,
+        row: u64,
+        source: RowError,
+    },
+    Template {
+        name: String,
+        source: tinytemplate::error::Error,
+    },
+    Glob {
+        pattern: String,
+        source: glob::PatternError,
+    },
+    NoInputs {
+        input: String,
+    },
+    Schema {
+        path: String,
+        version: u32,
+    },
+    ThreadPool(rayon::ThreadPoolBuildError),
+    Store {
+        path: String,
+        source: rusqlite::Error,
+    },
+    Json {
+        path: String,
+        source: serde_json::Error,
+    },
+    Snapshot {
+        path: String,
+        reason: String,
+    },
+    Block {
+        path: String,
+        reason: String,
+    },
+    Pools {
+        path: String,
+        reason: String,
+    },
+    Views {
+        path: String,
+        reason: String,
+    },
+    Malformed {
+        count: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::Csv { path, source } => write!(f, "{}: {}", path, source),
+            Error::Row { path, row, source } => write!(f, "{}, row {}: {}", path, row, source),
+            Error::Template { name, source } => {
+                write!(f, "could not render template '{}': {}", name, source)
+            }
+            Error::Glob { pattern, source } => {
+                write!(f, "invalid glob pattern '{}': {}", pattern, source)
+            }
+            Error::NoInputs { input } => write!(f, "{}: no input files found", input),
+            Error::ThreadPool(source) => write!(f, "could not start thread pool: {}", source),
+            Error::Store { path, source } => write!(f, "{}: {}", path, source),
+            Error::Json { path, source } => write!(f, "{}: {}", path, source),
+            Error::Snapshot { path, reason } => {
+                write!(f, "{}: invalid mempool snapshot: {}", path, reason)
+            }
+            Error::Block { path, reason } => write!(f, "{}: {}", path, reason),
+            Error::Pools { path, reason } => {
+                write!(f, "{}: invalid pool definitions: {}", path, reason)
+            }
+            Error::Views { path, reason } => {
+                write!(f, "{}: invalid view definitions: {}", path, reason)
+            }
+            Error::Malformed { count } => {
+                write!(f, "found {} malformed replacement events", count)
+            }
+            Error::Schema { path, version } => write!(
+                f,
+                "{}: unsupported schema version {} (latest supported version is {})",
+                path,
+                version,
+                crate::types::SCHEMA_VERSION
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Csv { source, .. } => Some(source),
+            Error::Row { source, .. } => Some(source),
+            Error::Template { source, .. } => Some(source),
+            Error::Glob { source, .. } => Some(source),
+            Error::NoInputs { .. } => None,
+            Error::Schema { .. } => None,
+            Error::ThreadPool(source) => Some(source),
+            Error::Store { source, .. } => Some(source),
+            Error::Json { source, .. } => Some(source),
+            Error::Snapshot { .. }
+            | Error::Block { .. }
+            | Error::Pools { .. }
+            | Error::Views { .. }
+            | Error::Malformed { .. } => None,
+        }
+    }
+}
+
+// An error caused by the contents of a single replacement event. These are
+// wrapped into an Error::Row together with the file and row they occurred in.
+#[derive(Debug)]
+pub enum RowError {
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    Field {
+        column: String,
+        reason: String,
+    },
+    Hex {
+        column: String,
+        reason: String,
+    },
+    Decode {
+        column: &'static str,
+        txid: String,
+        source: bitcoin::consensus::encode::Error,
+    },
+    Analysis {
+        column: &'static str,
+        txid: String,
+        source: bitcoin::blockdata::script::Error,
+    },
+    TxidMismatch {
+        column: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl RowError {
+    // The column the error occurred in, if it can be attributed to one.
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            RowError::Csv(_) => None,
+            RowError::Json(_) => None,
+            RowError::Field { column, .. } => Some(column),
+            RowError::Hex { column, .. } => Some(column),
+            RowError::Decode { column, .. } => Some(column),
+            RowError::Analysis { column, .. } => Some(column),
+            RowError::TxidMismatch { column, .. } => Some(column),
+        }
+    }
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RowError::Csv(source) => write!(f, "{}", source),
+            RowError::Json(source) => write!(f, "{}", source),
+            RowError::Field { column, reason } => {
+                write!(f, "invalid value in column '{}': {}", column, reason)
+            }
+            RowError::Hex { column, reason } => {
+                write!(f, "invalid hex in column '{}': {}", column, reason)
+            }
+            RowError::Decode {
+                column,
+                txid,
+                source,
+            } => write!(
+                f,
+                "could not decode transaction {} from column '{}': {}",
+                txid, column, source
+            ),
+            RowError::Analysis { txid, source, .. } => {
+                write!(f, "could not analyze transaction {}: {}", txid, source)
+            }
+            RowError::TxidMismatch {
+                column,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "txid {} in column '{}' does not match the raw transaction's txid {}",
+                expected, column, actual
+            ),
+        }
+    }
+}
+
+impl error::Error for RowError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RowError::Csv(source) => Some(source),
+            RowError::Json(source) => Some(source),
+            RowError::Field { .. } => None,
+            RowError::Hex { .. } => None,
+            RowError::Decode { source, .. } => Some(source),
+            RowError::Analysis { source, .. } => Some(source),
+            RowError::TxidMismatch { .. } => None,
+        }
+    }
+}
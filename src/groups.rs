@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use crate::html;
+use crate::rbf::ReplacementClass;
+
+type GroupFilter<'a> = Box<dyn Fn(&html::ReplacementGroupContext) -> bool + 'a>;
+
+// Groups replacement events by their replacement transaction and timestamp
+// while only keeping the newest `limit` groups in memory. Groups older than
+// the kept ones are dropped as soon as they are known to not be rendered.
+pub struct NewestReplacementGroups<'a> {
+    class: ReplacementClass,
+    limit: usize,
+    groups: BTreeMap<(u64, String), (html::TransactionContext, Vec<html::TransactionContext>)>,
+    // Only groups matching the filter are kept. A group is checked once it's
+    // complete, i.e. once an event with a newer timestamp was read.
+    filter: Option<GroupFilter<'a>>,
+    newest: u64,
+}
+
+impl<'a> NewestReplacementGroups<'a> {
+    pub fn new(class: ReplacementClass, limit: usize) -> Self {
+        NewestReplacementGroups {
+            class,
+            limit,
+            groups: BTreeMap::new(),
+            filter: None,
+            newest: 0,
+        }
+    }
+
+    pub fn with_filter(
+        class: ReplacementClass,
+        limit: usize,
+        filter: impl Fn(&html::ReplacementGroupContext) -> bool + 'a,
+    ) -> Self {
+        NewestReplacementGroups {
+            filter: Some(Box::new(filter)),
+            ..NewestReplacementGroups::new(class, limit)
+        }
+    }
+
+    // Drops the groups in the key range that don't match the filter.
+    fn check(&mut self, from: (u64, String), to: (u64, String)) {
+        let Some(filter) = &self.filter else {
+            return;
+        };
+        let failed: Vec<(u64, String)> = self
+            .groups
+            .range(from..to)
+            .filter(|((timestamp, _), (replacement, replaced))| {
+                !filter(&build_replacement_group(
+                    self.class,
+                    *timestamp,
+                    replacement.clone(),
+                    replaced.clone(),
+                ))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in failed {
+            self.groups.remove(&key);
+        }
+    }
+
+    pub fn insert(&mut self, replacement: &html::ReplacementContext) {
+        let key = (replacement.timestamp, replacement.replacement.txid.clone());
+
+        // The groups of older events are complete once an event with a newer
+        // timestamp is read.
+        if key.0 > self.newest {
+            self.check((self.newest, String::new()), (key.0, String::new()));
+            self.newest = key.0;
+        }
+
+        if let Some((_, replaced)) = self.groups.get_mut(&key) {
+            // The same event might be logged more than once, e.g. when
+            // the logger rotates files.
+            if !replaced.contains(&replacement.replaced) {
+                replaced.push(replacement.replaced.clone());
+            }
+        } else {
+            self.groups.insert(
+                key.clone(),
+                (
+                    replacement.replacement.clone(),
+                    vec![replacement.replaced.clone()],
+                ),
+            );
+        }
+        // Groups of events read out of order are checked right away.
+        if key.0 < self.newest {
+            self.check(key.clone(), (key.0, format!("{}\0", key.1)));
+        }
+
+        // Groups that aren't complete yet might still be dropped by the
+        // filter and don't count towards the limit.
+        let pending = match self.filter {
+            Some(_) => self.groups.range((self.newest, String::new())..).count(),
+            None => 0,
+        };
+        while self.groups.len() > self.limit.saturating_add(pending) {
+            self.groups.pop_first();
+        }
+    }
+
+    // Returns the replacement groups ordered from newest to oldest.
+    pub fn into_groups(mut self) -> Vec<html::ReplacementGroupContext> {
+        // The groups of the newest events are complete now.
+        self.check((self.newest, String::new()), (u64::MAX, String::new()));
+        // Pending groups that matched the filter can exceed the limit.
+        self.groups
+            .into_iter()
+            .rev()
+            .take(self.limit)
+            .map(|((timestamp, _), (replacement, replaced))| {
+                build_replacement_group(self.class, timestamp, replacement, replaced)
+            })
+            .collect()
+    }
+}
+
+fn build_replacement_group(
+    class: ReplacementClass,
+    timestamp: u64,
+    replacement: html::TransactionContext,
+    replaced: Vec<html::TransactionContext>,
+) -> html::ReplacementGroupContext {
+    let replaced_fee = replaced.iter().map(|tx| tx.fee).sum::<u64>();
+    let replaced_vsize = replaced.iter().map(|tx| tx.vsize).sum::<u64>();
+    html::ReplacementGroupContext {
+        event: class.event().to_string(),
+        timestamp,
+        delta: html::ReplacementGroupDeltaContext {
+            fee: replacement.fee as i64 - replaced_fee as i64,
+            vsize: replacement.vsize as i64 - replaced_vsize as i64,
+            feerate: if replaced.len() > 1 {
+                String::new()
+            } else {
+                format!(
+                    "+{:.2} sat/vByte",
+                    (replacement.fee as f64) / (replacement.vsize as f64)
+                        - (replaced_fee as f64 / replaced_vsize as f64)
+                )
+            },
+        },
+        replaced,
+        replacement,
+        chain: None,
+        outcome: None,
+    }
+}
+
+// Adds the groups to the groups of each of their transactions. Groups that
+// are already indexed, e.g. because they are rendered in multiple views, are
+// skipped.
+pub fn index_by_txid(
+    groups: &[html::ReplacementGroupContext],
+    index: &mut BTreeMap<String, Vec<html::ReplacementGroupContext>>,
+) {
+    for group in groups {
+        let txids = group
+            .replaced
+            .iter()
+            .chain([&group.replacement])
+            .map(|tx| tx.txid.clone());
+        for txid in txids {
+            let tx_groups = index.entry(txid).or_default();
+            if !tx_groups.iter().any(|g| {
+                g.timestamp == group.timestamp && g.replacement.txid == group.replacement.txid
+            }) {
+                tx_groups.push(group.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{replacement, txid};
+
+    fn keys(groups: &[html::ReplacementGroupContext]) -> Vec<(u64, String, usize)> {
+        groups
+            .iter()
+            .map(|g| (g.timestamp, g.replacement.txid.clone(), g.replaced.len()))
+            .collect()
+    }
+
+    fn key(timestamp: u64, replacement: u8, replaced: usize) -> (u64, String, usize) {
+        (timestamp, txid(replacement).to_string(), replaced)
+    }
+
+    // Groups replacing more than one transaction.
+    fn multi_replaced(group: &html::ReplacementGroupContext) -> bool {
+        group.replaced.len() > 1
+    }
+
+    #[test]
+    fn keeps_newest_groups() {
+        let mut groups = NewestReplacementGroups::new(ReplacementClass::FullRbf, 2);
+        groups.insert(&replacement(10, 1, 2));
+        groups.insert(&replacement(20, 3, 4));
+        groups.insert(&replacement(30, 5, 6));
+        // Older than all kept groups.
+        groups.insert(&replacement(5, 7, 8));
+        assert_eq!(
+            keys(&groups.into_groups()),
+            vec![key(30, 6, 1), key(20, 4, 1)]
+        );
+    }
+
+    #[test]
+    fn out_of_order_event_replaces_oldest_group() {
+        let mut groups = NewestReplacementGroups::new(ReplacementClass::FullRbf, 2);
+        groups.insert(&replacement(10, 1, 2));
+        groups.insert(&replacement(30, 3, 4));
+        groups.insert(&replacement(20, 5, 6));
+        assert_eq!(
+            keys(&groups.into_groups()),
+            vec![key(30, 4, 1), key(20, 6, 1)]
+        );
+    }
+
+    #[test]
+    fn groups_events_and_skips_duplicates() {
+        let mut groups = NewestReplacementGroups::new(ReplacementClass::OptIn, 10);
+        groups.insert(&replacement(10, 1, 9));
+        groups.insert(&replacement(10, 2, 9));
+        groups.insert(&replacement(10, 1, 9));
+        // The same replacement at another time is another group.
+        groups.insert(&replacement(20, 3, 9));
+        let groups = groups.into_groups();
+        assert_eq!(keys(&groups), vec![key(20, 9, 1), key(10, 9, 2)]);
+        assert_eq!(groups[1].event, ReplacementClass::OptIn.event());
+        assert_eq!(groups[1].delta.fee, 9000 - 3000);
+        assert_eq!(groups[1].delta.vsize, 100 - 200);
+    }
+
+    #[test]
+    fn filter_keeps_newest_matching_groups() {
+        let mut groups =
+            NewestReplacementGroups::with_filter(ReplacementClass::FullRbf, 1, multi_replaced);
+        groups.insert(&replacement(10, 1, 2));
+        groups.insert(&replacement(10, 3, 2));
+        // Newer groups that don't match don't evict the matching one.
+        groups.insert(&replacement(20, 4, 5));
+        groups.insert(&replacement(30, 6, 7));
+        assert_eq!(keys(&groups.into_groups()), vec![key(10, 2, 2)]);
+    }
+
+    #[test]
+    fn filter_checks_pending_groups_once_complete() {
+        let mut groups =
+            NewestReplacementGroups::with_filter(ReplacementClass::FullRbf, 1, multi_replaced);
+        // Both groups at the newest timestamp are pending and don't count
+        // towards the limit, although only one matches in the end.
+        groups.insert(&replacement(10, 1, 2));
+        groups.insert(&replacement(10, 3, 4));
+        groups.insert(&replacement(10, 5, 4));
+        assert_eq!(keys(&groups.into_groups()), vec![key(10, 4, 2)]);
+    }
+
+    #[test]
+    fn filter_returns_at_most_limit_groups() {
+        let mut groups =
+            NewestReplacementGroups::with_filter(ReplacementClass::FullRbf, 1, multi_replaced);
+        groups.insert(&replacement(10, 1, 2));
+        groups.insert(&replacement(10, 3, 2));
+        // Pending while newest, so the matching older group isn't evicted.
+        groups.insert(&replacement(20, 4, 5));
+        groups.insert(&replacement(20, 6, 5));
+        assert_eq!(keys(&groups.into_groups()), vec![key(20, 5, 2)]);
+    }
+
+    #[test]
+    fn filter_checks_out_of_order_events_right_away() {
+        let mut groups =
+            NewestReplacementGroups::with_filter(ReplacementClass::FullRbf, 2, multi_replaced);
+        groups.insert(&replacement(10, 1, 2));
+        groups.insert(&replacement(10, 3, 2));
+        groups.insert(&replacement(30, 4, 5));
+        groups.insert(&replacement(30, 6, 5));
+        // Complete when read, so it's dropped right away instead of evicting
+        // the group at 10.
+        groups.insert(&replacement(20, 7, 8));
+        groups.insert(&replacement(40, 9, 10));
+        assert_eq!(
+            keys(&groups.into_groups()),
+            vec![key(30, 5, 2), key(10, 2, 2)]
+        );
+    }
+
+    #[test]
+    fn filter_without_matches() {
+        let mut groups =
+            NewestReplacementGroups::with_filter(ReplacementClass::FullRbf, 5, |_| false);
+        groups.insert(&replacement(10, 1, 2));
+        groups.insert(&replacement(20, 3, 4));
+        assert!(groups.into_groups().is_empty());
+    }
+}
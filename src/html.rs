@@ -1,8 +1,8 @@
 use std::hash::{Hash, Hasher};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TransactionContext {
     pub txid: String,
     pub fee: u64,
@@ -14,6 +14,33 @@ pub struct TransactionContext {
     pub outputs: Vec<String>,
     pub op_return: bool,
     pub optin_rbf: bool,
+    // TRUC (version 3) transactions are replaceable regardless of their
+    // nSequence values.
+    #[serde(default)]
+    pub truc: bool,
+    // Set if the transaction was found in the local blocks. Only looked up
+    // when rendering.
+    #[serde(default)]
+    pub mined: Option<MinedContext>,
+    // The local blocks were searched for the transaction. Otherwise, the mined
+    // status is loaded in the browser.
+    #[serde(default)]
+    pub mined_checked: bool,
+    // Only known for replaced transactions logged with schema version 2.
+    pub ancestor_fee: Option<u64>,
+    pub ancestor_vsize: Option<u64>,
+    pub ancestor_feerate: Option<String>,
+    // Only known for replacement transactions logged with schema version 2.
+    pub mempool_min_fee: Option<String>,
+    pub peer: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MinedContext {
+    pub height: u64,
+    pub hash: String,
+    // Name of the pool that mined the block, if known.
+    pub pool: Option<String>,
 }
 
 impl Hash for TransactionContext {
@@ -37,7 +64,7 @@ pub struct ReplacementGroupDeltaContext {
     pub feerate: String,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ReplacementContext {
     pub timestamp: u64,
     pub replaced: TransactionContext,
@@ -46,23 +73,204 @@ pub struct ReplacementContext {
 
 #[derive(Serialize, Clone)]
 pub struct ReplacementGroupContext {
+    // What kind of replacement the group is, e.g. "full-RBF event".
+    pub event: String,
     pub timestamp: u64,
     pub replaced: Vec<TransactionContext>,
     pub replacement: TransactionContext,
     pub delta: ReplacementGroupDeltaContext,
+    // Set if the replacement is part of a chain of replacements.
+    pub chain: Option<ChainLinkContext>,
+    // Only known when the mined status is looked up in local blocks.
+    pub outcome: Option<OutcomeContext>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct OutcomeContext {
+    pub name: String,
+    pub label: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct OutcomeFilterContext {
+    pub name: String,
+    pub title: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ChainLinkContext {
+    pub tip: String,
+    pub replacements: usize,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ChainStepContext {
+    pub txid: String,
+    pub fee: u64,
+    pub vsize: u64,
+    pub feerate: String,
+    // Fee change compared to the previous version.
+    pub fee_delta: i64,
+    // Seconds since the first replacement in the chain.
+    pub seconds: u64,
+}
+
+// A transaction that was replaced multiple times. The first step is the
+// original transaction, followed by its replacements.
+#[derive(Serialize, Clone)]
+pub struct ChainContext {
+    // The last replacement in the chain.
+    pub tip: String,
+    pub replacements: usize,
+    pub fee_delta: i64,
+    // Seconds between the first and the last replacement.
+    pub duration: u64,
+    // Timestamp of the first replacement.
+    pub timestamp: u64,
+    pub steps: Vec<ChainStepContext>,
+}
+
+// All rendered replacement groups a transaction is part of.
+#[derive(Serialize)]
+pub struct TxSiteContext {
+    pub txid: String,
+    pub replacements: Vec<ReplacementGroupContext>,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize)]
+pub struct ChainSiteContext {
+    pub chain: ChainContext,
+    pub timestamp: u64,
 }
 
 #[derive(Serialize)]
 pub struct NavigationContext {
+    // Path of the view the pages belong to, e.g. `/` or `/no_opreturn/`.
+    pub base: String,
     pub pages: Vec<u32>,
 }
 
+#[derive(Serialize, Clone)]
+pub struct ViewLinkContext {
+    pub title: String,
+    pub url: String,
+    pub active: bool,
+}
+
 #[derive(Serialize)]
 pub struct SiteContext {
     pub replacements: Vec<ReplacementGroupContext>,
+    // What kind of replacements are shown, e.g. "full-RBF replacements".
+    pub title: String,
+    // If the replacements are full-RBF replacements, which the page explains.
+    pub full_rbf: bool,
     pub timestamp: u64,
     pub page: u32,
     pub navigation: NavigationContext,
+    // All rendered views, to switch between them.
+    pub views: Vec<ViewLinkContext>,
+    // Outcomes the replacements can be filtered by. Empty if the outcomes
+    // aren't known, i.e. the mined status wasn't looked up in local blocks.
+    pub outcomes: Vec<OutcomeFilterContext>,
+}
+
+// The newest replacement groups of all pages, written to `latest.json`.
+#[derive(Serialize)]
+pub struct LatestContext<'a> {
+    pub title: &'a str,
+    pub timestamp: u64,
+    pub replacements: &'a [ReplacementGroupContext],
+}
+
+#[derive(Serialize)]
+pub struct FeedEntryContext {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub updated: String,
+    pub replaced: Vec<String>,
+    pub replacement: String,
+    pub fee_delta: i64,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize)]
+pub struct FeedContext {
+    pub title: String,
+    pub id: String,
+    // URL of the HTML pages.
+    pub url: String,
+    pub feed_url: String,
+    pub updated: String,
+    pub entries: Vec<FeedEntryContext>,
+}
+
+#[derive(Serialize)]
+pub struct BarContext {
+    pub x: String,
+    pub y: String,
+    pub width: String,
+    pub height: String,
+    pub label_x: String,
+    // Empty for bars that aren't labeled.
+    pub label: String,
+    pub tooltip: String,
+}
+
+#[derive(Serialize)]
+pub struct BarChartContext {
+    pub title: String,
+    pub width: f64,
+    pub height: f64,
+    pub label_y: f64,
+    // The value of the highest bar.
+    pub max: u64,
+    pub bars: Vec<BarContext>,
+}
+
+#[derive(Serialize)]
+pub struct ClassCountContext {
+    pub name: String,
+    pub title: String,
+    pub count: u64,
+}
+
+#[derive(Serialize)]
+pub struct StatsSiteContext {
+    pub timestamp: u64,
+    // Number of full-RBF replacements.
+    pub count: u64,
+    pub op_return: u64,
+    pub op_return_share: String,
+    pub classes: Vec<ClassCountContext>,
+    pub charts: Vec<BarChartContext>,
+}
+
+#[derive(Serialize)]
+pub struct PoolAdoptionContext {
+    pub name: String,
+    // Full-RBF replacements mined by the pool.
+    pub replacement_mined: u64,
+    // Replacements of which the pool mined a replaced transaction instead.
+    pub replaced_mined: u64,
+    // Percentage of these replacements in which the pool mined the
+    // replacement.
+    pub share: String,
+}
+
+#[derive(Serialize)]
+pub struct AdoptionWindowContext {
+    pub name: String,
+    // Number of replacement groups in the window.
+    pub replacements: usize,
+    pub pools: Vec<PoolAdoptionContext>,
+}
+
+#[derive(Serialize)]
+pub struct AdoptionSiteContext {
+    pub timestamp: u64,
+    pub windows: Vec<AdoptionWindowContext>,
 }
 
 pub static TEMPLATE_TX: &str = r###"
@@ -74,11 +282,20 @@ pub static TEMPLATE_TX: &str = r###"
         {{ if optin_rbf }}
             <span class="badge text-bg-primary">opt-in RBF</span>
         {{ endif }}
-        <a href="#" class="badge text-bg-light badge-mined text-decoration-none" target="_blank" aria-txid="{txid}">loading..</a>
+        {{ if truc }}
+            <span class="badge text-bg-info">TRUC</span>
+        {{ endif }}
+        {{ if mined }}
+            <a href="https://miningpool.observer/template-and-block/{mined.hash}" class="badge text-bg-warning text-decoration-none" target="_blank">mined in {mined.height}{{ if mined.pool }} by {mined.pool}{{ endif }}</a>
+        {{ else }}
+            {{ if not mined_checked }}
+                <a href="#" class="badge text-bg-light badge-mined text-decoration-none" target="_blank" aria-txid="{txid}">loading..</a>
+            {{ endif }}
+        {{ endif }}
     </div>
     <div class="col-12">
         <div class="row">
-            <span class="col-12 text-truncate">{txid}</span>
+            <a class="col-12 text-truncate text-decoration-none" href="/tx/{txid}.html">{txid}</a>
             <span class="col-md-2 col-4 text-muted">feerate</span><span class="col-md-4 col-8">{feerate} sat/vByte</span>
             <span class="col-md-2 col-4 text-muted">fee</span><span class="col-md-4 col-8">{fee} sat</span>
             <span class="col-md-2 col-4 text-muted">vsize</span><span class="col-md-4 col-8">{vsize} vByte</span>
@@ -87,6 +304,16 @@ pub static TEMPLATE_TX: &str = r###"
             {{ else }}
                 <span class="col-md-2 col-4 text-muted"></span><span class="col-md-4 col-8"></span>
             {{ endif }}
+            {{ if ancestor_feerate }}
+                <span class="col-md-2 col-4 text-muted">ancestor feerate</span><span class="col-md-4 col-8">{ancestor_feerate} sat/vByte</span>
+                <span class="col-md-2 col-4 text-muted">ancestor fee</span><span class="col-md-4 col-8">{ancestor_fee} sat ({ancestor_vsize} vByte)</span>
+            {{ endif }}
+            {{ if mempool_min_fee }}
+                <span class="col-md-2 col-4 text-muted">mempool min fee</span><span class="col-md-4 col-8">{mempool_min_fee} sat/vByte</span>
+            {{ endif }}
+            {{ if peer }}
+                <span class="col-md-2 col-4 text-muted">relayed by</span><span class="col-md-4 col-8 text-truncate">{peer}</span>
+            {{ endif }}
             <span class="col-md-2 col-4 text-muted">inputs</span><span class="col-md-10 col-8">{{ for input in inputs }}{input}{{ if not @last }}, {{ endif }}{{ endfor }}</span>
             <span class="col-md-2 col-4 text-muted">outputs</span><span class="col-md-10 col-8">{{ for output in outputs }}{output}{{ if not @last }}, {{ endif }}{{ endfor }}</span>
         </div>
@@ -117,11 +344,18 @@ pub static TEMPLATE_DELTAS: &str = r#"
 "#;
 
 pub static TEMPLATE_REPLACEMENT: &str = r#"
-<div class="card m-3 replacement-card" id="replacement-{replacement.txid}">
+<div class="card m-3 replacement-card{{ if replacement.mined }} replacement-mined text-bg-warning{{ endif }}{{ if outcome }} outcome-{outcome.name}{{ endif }}" id="replacement-{replacement.txid}">
     <div class="card-header">
         <div class="col-12">
-            full RBF event
+            {event}
             <span class="timestamp" aria-timestamp="{timestamp}">timestamp</span>
+            <a href="/tx/{replacement.txid}.html" class="text-decoration-none">permalink</a>
+            {{ if outcome }}
+                <span class="badge text-bg-dark">{outcome.label}</span>
+            {{ endif }}
+            {{ if chain }}
+                <a href="/chains/{chain.tip}.html" class="badge text-bg-light text-decoration-none">part of a chain of {chain.replacements} replacements</a>
+            {{ endif }}
         </div>
     </div>
     <div class="card-body">
@@ -162,13 +396,13 @@ pub static TEMPLATE_PAGE_NAVIGATION: &str = r###"
         {{ for page in pages }}
             {{ if not page }}
                 <li class="page-item">
-                    <a href="/">
+                    <a href="{base}">
                         <span class="page-link">{page}</span>
                     </a>
                 </li>
             {{ else }}
                 <li class="page-item">
-                    <a href="page_{page}.html">
+                    <a href="{base}page_{page}.html">
                         <span class="page-link">{page}</span>
                     </a>
                 </li>
@@ -178,37 +412,7 @@ pub static TEMPLATE_PAGE_NAVIGATION: &str = r###"
 </nav>
 "###;
 
-pub static TEMPLATE_SITE: &str = r###"
-<!doctype html>
-<html lang="en">
-  <head>
-    <meta charset="utf-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1">
-    <meta name="description" content="Showing recent full-RBF replacements">
-    <meta name="author" content="0xB10C">
-    <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.3/dist/css/bootstrap.min.css" rel="stylesheet" integrity="sha384-rbsA2VBKQhggwzxH7pPCaAqO46MgnOM80zW1RWuH61DGLwZJEdK2Kadq2F9CUG65" crossorigin="anonymous">
-    <title>Recent full-RBF replacements {{ if page }}(page {page}){{ endif }} - mempool.observer</title>
-
-    <style>
-        .replacement-mined \{
-            background-color: red;
-        }
-        .replacement-card-hidden \{
-            display: none;
-        }
-        @keyframes blink \{
-            0% \{ opacity: 1; }
-            50% \{ opacity: 0.6; background-color: red; }
-            100% \{ pacity: 1; }
-        }
-        .blink \{
-            animation: blink 1s ease 0.5s 1 normal none;
-        }
-    </style>
-
-  </head>
-  <body class="container-fluid">
-
+pub static TEMPLATE_HEADER: &str = r###"
   <header>
     <nav class="navbar border-bottom mb-3">
         <div class="">
@@ -239,46 +443,10 @@ pub static TEMPLATE_SITE: &str = r###"
         </div>
     </nav>
   </header>
+"###;
 
-  <main>
-
-    <div class="container-fluid mx-lg-5">
-        <h1 class="lh-1 mb-3">Recent full-RBF replacements {{if page }}(page {page}){{ endif }}</h1>
-        <p class="lead">
-            Showing recent full-RBF replacement events my <code>mempoolfullrbf=1</code> node saw.
-        </p>
-        <p>
-            I assume that a replacement is a full-RBF replacement, if the replaced transaction does not signal BIP-125 replaceability and the replaced transaction directly conflicts with the replacement*.
-            Transactions that confirmed in a block (queried from the blockstream.info API) are labeled as <span class="badge text-bg-warning">mined in X</span>.
-            Clicking on the badge shows the block and the pool (if known) that mined the transaction.
-            A replacement being mined could mean, that the pool has full-RBF enabled.
-            <br>
-            <label>Only show mined full-RBF replacements (on this page):</label>
-            <button class="btn btn-sm btn-warning" onclick=toggleVisibilty()>toggle</button>
-        </p>
-        <p class="small text-muted">
-            *There are cases where a child does not signal optin-RBF, but can still be replaced if a parent is replaced. This is not a full-RBF replacement though.
-        </p>
-
-    </div>
-
-    <div class="mx-lg-5">
-        {{- call tmpl_navigation with navigation -}}
-
-        {{ for replacement in replacements }}
-            {{- call tmpl_replacement with replacement -}}
-        {{ endfor }}
-
-        {{- call tmpl_navigation with navigation -}}
-    </div>
-
-  </main>
-  <footer class="text-muted border-top">
-    <p class="mx-lg-5">
-        by <a href="https://b10c.me">0xb10c</a> | site generated at <span class="timestamp" aria-timestamp="{timestamp}">timestamp</span> with <a href="https://github.com/0xB10C/mempool-observer-fullrbf-ui">github.com/0xB10C/mempool-observer-fullrbf-ui</a>
-    </p>
-  </footer>
-
+// Formats timestamps and loads the mined status of transactions.
+pub static TEMPLATE_SCRIPT: &str = r###"
 <script>
 
     // from https://blog.webdevsimplified.com/2020-07/relative-time-format/:
@@ -309,6 +477,17 @@ pub static TEMPLATE_SITE: &str = r###"
         }
     }
 
+    function filterOutcome(outcome) \{
+        let cards = document.getElementsByClassName("replacement-card");
+        for (card of cards) \{
+            if (outcome === "" || card.classList.contains("outcome-" + outcome)) \{
+                card.classList.remove("replacement-card-hidden")
+            } else \{
+                card.classList.add("replacement-card-hidden")
+            }
+        }
+    }
+
     function toggleVisibilty() \{
         let cards = document.getElementsByClassName("replacement-card");
         for (card of cards) \{
@@ -402,7 +581,343 @@ pub static TEMPLATE_SITE: &str = r###"
     }
 
 </script>
+"###;
+
+pub static TEMPLATE_SITE: &str = r###"
+<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <meta name="description" content="Showing recent {title}">
+    <meta name="author" content="0xB10C">
+    <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.3/dist/css/bootstrap.min.css" rel="stylesheet" integrity="sha384-rbsA2VBKQhggwzxH7pPCaAqO46MgnOM80zW1RWuH61DGLwZJEdK2Kadq2F9CUG65" crossorigin="anonymous">
+    <link rel="alternate" type="application/atom+xml" title="Recent {title}" href="feed.xml">
+    <title>Recent {title} {{ if page }}(page {page}){{ endif }} - mempool.observer</title>
 
+    <style>
+        .replacement-mined \{
+            background-color: red;
+        }
+        .replacement-card-hidden \{
+            display: none;
+        }
+        @keyframes blink \{
+            0% \{ opacity: 1; }
+            50% \{ opacity: 0.6; background-color: red; }
+            100% \{ pacity: 1; }
+        }
+        .blink \{
+            animation: blink 1s ease 0.5s 1 normal none;
+        }
+    </style>
+
+  </head>
+  <body class="container-fluid">
+
+  {{- call tmpl_header with timestamp -}}
+
+  <main>
+
+    <div class="container-fluid mx-lg-5">
+        <h1 class="lh-1 mb-3">Recent {title} {{if page }}(page {page}){{ endif }}</h1>
+        <p class="lead">
+            Showing recent {title} my <code>mempoolfullrbf=1</code> node saw.
+            See the <a href="/stats.html">statistics</a> over all events.
+        </p>
+        <p>
+            {{ if full_rbf }}
+                I assume that a replacement is a full-RBF replacement, if the replaced transaction does not signal BIP-125 replaceability, is not a TRUC (version 3) transaction and the replaced transaction directly conflicts with the replacement*.
+            {{ endif }}
+            Transactions that confirmed in a block ({{ if outcomes }}looked up in the blocks read when rendering the site{{ else }}queried from the blockstream.info API{{ endif }}) are labeled as <span class="badge text-bg-warning">mined in X</span>.
+            Clicking on the badge shows the block and the pool (if known) that mined the transaction.
+            {{ if full_rbf }}
+                A replacement being mined could mean, that the pool has full-RBF enabled.
+            {{ endif }}
+            <br>
+            <label>Only show mined replacements (on this page):</label>
+            <button class="btn btn-sm btn-warning" onclick=toggleVisibilty()>toggle</button>
+            {{ if outcomes }}
+                <br>
+                <label>Only show replacements with the outcome (on this page):</label>
+                <button class="btn btn-sm btn-outline-dark" onclick="filterOutcome('')">all</button>
+                {{ for outcome in outcomes }}
+                    <button class="btn btn-sm btn-outline-dark" onclick="filterOutcome('{outcome.name}')">{outcome.title}</button>
+                {{ endfor }}
+            {{ endif }}
+        </p>
+        {{ if full_rbf }}
+            <p class="small text-muted">
+                *There are cases where a child does not signal optin-RBF, but can still be replaced if a parent is replaced. This is not a full-RBF replacement though.
+            </p>
+        {{ endif }}
+
+    </div>
+
+    <div class="mx-lg-5">
+        <ul class="nav nav-pills justify-content-center mb-3">
+            {{ for view in views }}
+                <li class="nav-item">
+                    <a class="nav-link{{ if view.active }} active{{ endif }}" href="{view.url}">{view.title}</a>
+                </li>
+            {{ endfor }}
+        </ul>
+
+        {{- call tmpl_navigation with navigation -}}
+
+        {{ for replacement in replacements }}
+            {{- call tmpl_replacement with replacement -}}
+        {{ endfor }}
+
+        {{- call tmpl_navigation with navigation -}}
+    </div>
+
+  </main>
+  <footer class="text-muted border-top">
+    <p class="mx-lg-5">
+        by <a href="https://b10c.me">0xb10c</a> | site generated at <span class="timestamp" aria-timestamp="{timestamp}">timestamp</span> with <a href="https://github.com/0xB10C/mempool-observer-fullrbf-ui">github.com/0xB10C/mempool-observer-fullrbf-ui</a>
+    </p>
+  </footer>
+
+{{- call tmpl_script with timestamp -}}
+
+</body>
+</html>
+"###;
+
+pub static TEMPLATE_CHAIN_SITE: &str = r###"
+<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <meta name="description" content="A chain of replacements">
+    <meta name="author" content="0xB10C">
+    <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.3/dist/css/bootstrap.min.css" rel="stylesheet" integrity="sha384-rbsA2VBKQhggwzxH7pPCaAqO46MgnOM80zW1RWuH61DGLwZJEdK2Kadq2F9CUG65" crossorigin="anonymous">
+    <title>Chain of {chain.replacements} replacements - mempool.observer</title>
+  </head>
+  <body class="container-fluid">
+
+  {{- call tmpl_header with timestamp -}}
+
+  <main>
+    <div class="container-fluid mx-lg-5">
+        <h1 class="lh-1 mb-3">Chain of {chain.replacements} replacements</h1>
+        <p class="lead">
+            The transaction was replaced {chain.replacements} times over {chain.duration} seconds, starting at UTC timestamp {chain.timestamp}.
+            The fee changed by {chain.fee_delta} sat in total.
+        </p>
+        <p><a href="/">back to the recent replacements</a></p>
+        <table class="table">
+            <thead>
+                <tr>
+                    <th>version</th>
+                    <th>txid</th>
+                    <th>fee</th>
+                    <th>vsize</th>
+                    <th>feerate</th>
+                    <th>fee change</th>
+                    <th>seen after</th>
+                </tr>
+            </thead>
+            <tbody>
+            {{ for step in chain.steps }}
+                <tr>
+                    <td>{{ if @first }}original{{ else }}replacement {@index}{{ endif }}</td>
+                    <td class="text-truncate" style="max-width: 20em"><code>{step.txid}</code></td>
+                    <td>{step.fee} sat</td>
+                    <td>{step.vsize} vByte</td>
+                    <td>{step.feerate} sat/vByte</td>
+                    <td>{{ if @first }}{{ else }}{step.fee_delta} sat{{ endif }}</td>
+                    <td>{{ if @first }}{{ else }}{step.seconds} seconds{{ endif }}</td>
+                </tr>
+            {{ endfor }}
+            </tbody>
+        </table>
+    </div>
+  </main>
+  <footer class="text-muted border-top">
+    <p class="mx-lg-5">
+        by <a href="https://b10c.me">0xb10c</a> | site generated at UTC timestamp {timestamp} with <a href="https://github.com/0xB10C/mempool-observer-fullrbf-ui">github.com/0xB10C/mempool-observer-fullrbf-ui</a>
+    </p>
+  </footer>
+</body>
+</html>
+"###;
+
+pub static TEMPLATE_TX_SITE: &str = r###"
+<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <meta name="description" content="Replacements of transaction {txid}">
+    <meta name="author" content="0xB10C">
+    <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.3/dist/css/bootstrap.min.css" rel="stylesheet" integrity="sha384-rbsA2VBKQhggwzxH7pPCaAqO46MgnOM80zW1RWuH61DGLwZJEdK2Kadq2F9CUG65" crossorigin="anonymous">
+    <title>Transaction {txid} - mempool.observer</title>
+  </head>
+  <body class="container-fluid">
+
+  {{- call tmpl_header with timestamp -}}
+
+  <main>
+    <div class="container-fluid mx-lg-5">
+        <h1 class="lh-1 mb-3 text-truncate">Transaction {txid}</h1>
+        <p class="lead">
+            Replacements the transaction is part of.
+        </p>
+        <p><a href="/">back to the recent replacements</a></p>
+    </div>
+
+    <div class="mx-lg-5">
+        {{ for replacement in replacements }}
+            {{- call tmpl_replacement with replacement -}}
+        {{ endfor }}
+    </div>
+  </main>
+  <footer class="text-muted border-top">
+    <p class="mx-lg-5">
+        by <a href="https://b10c.me">0xb10c</a> | site generated at <span class="timestamp" aria-timestamp="{timestamp}">timestamp</span> with <a href="https://github.com/0xB10C/mempool-observer-fullrbf-ui">github.com/0xB10C/mempool-observer-fullrbf-ui</a>
+    </p>
+  </footer>
+
+{{- call tmpl_script with timestamp -}}
+
+</body>
+</html>
+"###;
+
+pub static TEMPLATE_FEED: &str = r###"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+    <title>{title} - mempool.observer</title>
+    <id>{id}</id>
+    <link rel="alternate" type="text/html" href="{url}"/>
+    <link rel="self" type="application/atom+xml" href="{feed_url}"/>
+    <updated>{updated}</updated>
+    <author>
+        <name>0xB10C</name>
+    </author>
+    {{ for entry in entries }}
+    <entry>
+        <title>{entry.title}</title>
+        <id>{entry.id}</id>
+        <link rel="alternate" type="text/html" href="{entry.url}"/>
+        <updated>{entry.updated}</updated>
+        <content type="html">
+            &lt;p&gt;replaced: {{ for txid in entry.replaced }}{txid}{{ if not @last }}, {{ endif }}{{ endfor }}&lt;/p&gt;
+            &lt;p&gt;replacement: {entry.replacement}&lt;/p&gt;
+            &lt;p&gt;fee delta: {entry.fee_delta} sat&lt;/p&gt;
+            &lt;p&gt;timestamp: {entry.timestamp} ({entry.updated})&lt;/p&gt;
+        </content>
+    </entry>
+    {{ endfor }}
+</feed>
+"###;
+
+pub static TEMPLATE_BAR_CHART: &str = r###"
+<figure class="m-3">
+    <figcaption>{title} (max. {max})</figcaption>
+    <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="100%" style="max-width: {width}px" role="img">
+        {{ for bar in bars }}
+        <rect x="{bar.x}" y="{bar.y}" width="{bar.width}" height="{bar.height}" fill="#c83737"><title>{bar.tooltip}</title></rect>
+        {{ if bar.label }}<text x="{bar.label_x}" y="{label_y}" font-size="10" text-anchor="middle" fill="#1a1a1a">{bar.label}</text>{{ endif }}
+        {{ endfor }}
+    </svg>
+</figure>
+"###;
+
+pub static TEMPLATE_STATS_SITE: &str = r###"
+<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <meta name="description" content="Statistics of full-RBF replacements">
+    <meta name="author" content="0xB10C">
+    <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.3/dist/css/bootstrap.min.css" rel="stylesheet" integrity="sha384-rbsA2VBKQhggwzxH7pPCaAqO46MgnOM80zW1RWuH61DGLwZJEdK2Kadq2F9CUG65" crossorigin="anonymous">
+    <title>Full-RBF replacement statistics - mempool.observer</title>
+  </head>
+  <body class="container-fluid">
+
+  {{- call tmpl_header with timestamp -}}
+
+  <main>
+    <div class="container-fluid mx-lg-5">
+        <h1 class="lh-1 mb-3">Full-RBF replacement statistics</h1>
+        <p class="lead">
+            Statistics over all {count} full-RBF replacement events my node saw.
+            {{ if op_return_share }}{op_return} ({op_return_share}%) of the replacements have an OP_RETURN output.{{ endif }}
+        </p>
+        <p><a href="/">back to the recent replacements</a></p>
+        <table class="table w-auto">
+            <thead>
+                <tr><th>replacement class</th><th>events</th></tr>
+            </thead>
+            <tbody>
+            {{ for class in classes }}
+                <tr><td>{class.title} (<code>{class.name}</code>)</td><td>{class.count}</td></tr>
+            {{ endfor }}
+            </tbody>
+        </table>
+        {{ for chart in charts }}
+            {{- call tmpl_bar_chart with chart -}}
+        {{ endfor }}
+    </div>
+  </main>
+  <footer class="text-muted border-top">
+    <p class="mx-lg-5">
+        by <a href="https://b10c.me">0xb10c</a> | site generated at UTC timestamp {timestamp} with <a href="https://github.com/0xB10C/mempool-observer-fullrbf-ui">github.com/0xB10C/mempool-observer-fullrbf-ui</a>
+    </p>
+  </footer>
+</body>
+</html>
+"###;
+
+pub static TEMPLATE_ADOPTION_SITE: &str = r###"
+<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <meta name="description" content="Full-RBF adoption by mining pool">
+    <meta name="author" content="0xB10C">
+    <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.3/dist/css/bootstrap.min.css" rel="stylesheet" integrity="sha384-rbsA2VBKQhggwzxH7pPCaAqO46MgnOM80zW1RWuH61DGLwZJEdK2Kadq2F9CUG65" crossorigin="anonymous">
+    <title>Full-RBF adoption by mining pool - mempool.observer</title>
+  </head>
+  <body class="container-fluid">
+
+  {{- call tmpl_header with timestamp -}}
+
+  <main>
+    <div class="container-fluid mx-lg-5">
+        <h1 class="lh-1 mb-3">Full-RBF adoption by mining pool</h1>
+        <p class="lead">
+            Pools ranked by the number of full-RBF replacements they mined compared to the number of replaced transactions they mined instead.
+            All full-RBF replacements read are included, not only the ones shown on the site.
+            A replacement of multiple transactions counts once, also if the pool mined more than one of them.
+            Also available as <a href="pools.csv">CSV</a>.
+        </p>
+        <p><a href="/">back to the recent replacements</a></p>
+        {{ for window in windows }}
+            <h2 class="h4 mt-4">{window.name} ({window.replacements} replacements)</h2>
+            <table class="table w-auto">
+                <thead>
+                    <tr><th>pool</th><th>replacements mined</th><th>replaced instead</th><th>replacement share</th></tr>
+                </thead>
+                <tbody>
+                {{ for pool in window.pools }}
+                    <tr><td>{pool.name}</td><td>{pool.replacement_mined}</td><td>{pool.replaced_mined}</td><td>{pool.share}%</td></tr>
+                {{ endfor }}
+                </tbody>
+            </table>
+        {{ endfor }}
+    </div>
+  </main>
+  <footer class="text-muted border-top">
+    <p class="mx-lg-5">
+        by <a href="https://b10c.me">0xb10c</a> | site generated at UTC timestamp {timestamp} with <a href="https://github.com/0xB10C/mempool-observer-fullrbf-ui">github.com/0xB10C/mempool-observer-fullrbf-ui</a>
+    </p>
+  </footer>
 </body>
 </html>
 "###;
@@ -0,0 +1,421 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use rawtx_rs::bitcoin::Txid;
+use serde::Deserialize;
+
+use crate::error::{Error, RowError};
+use crate::types::{RBFEvent, SCHEMA_VERSION};
+
+// File extensions of replacement event files picked up from directories.
+const INPUT_EXTENSIONS: [&str; 9] = [
+    ".csv",
+    ".csv.gz",
+    ".csv.zst",
+    ".jsonl",
+    ".jsonl.gz",
+    ".jsonl.zst",
+    ".ndjson",
+    ".ndjson.gz",
+    ".ndjson.zst",
+];
+
+// Input path to read replacement events from stdin.
+pub const STDIN: &str = "-";
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    Csv,
+    // One JSON object per line.
+    Jsonl,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(InputFormat::Csv),
+            "jsonl" => Ok(InputFormat::Jsonl),
+            _ => Err(format!("unknown input format '{}'", s)),
+        }
+    }
+}
+
+impl InputFormat {
+    // Detects the format of an input from its file extension. Defaults to CSV,
+    // e.g. for stdin.
+    pub fn detect(path: &str) -> InputFormat {
+        let path = path.trim_end_matches(".gz").trim_end_matches(".zst");
+        if path.ends_with(".jsonl") || path.ends_with(".ndjson") {
+            InputFormat::Jsonl
+        } else {
+            InputFormat::Csv
+        }
+    }
+}
+
+// A replacement event read from an input and the row (line) it was read from.
+pub struct EventRow {
+    pub row: u64,
+    pub event: Result<RBFEvent, RowError>,
+}
+
+fn is_glob(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+fn has_input_extension(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| INPUT_EXTENSIONS.iter().any(|e| n.ends_with(e)))
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> Error + '_ {
+    move |e| Error::Io {
+        path: path.display().to_string(),
+        source: e,
+    }
+}
+
+// Expands the input arguments into a list of files to read replacement events
+// from. An input can be a file, a directory (all files with a known extension
+// in it are read), a glob pattern or STDIN.
+pub fn expand_input_paths(inputs: &[String]) -> Result<Vec<String>, Error> {
+    let mut paths: Vec<String> = Vec::new();
+    for input in inputs {
+        let mut input_paths: Vec<String> = Vec::new();
+        if input == STDIN {
+            input_paths.push(input.clone());
+        } else if is_glob(input) {
+            let entries = glob::glob(input).map_err(|e| Error::Glob {
+                pattern: input.clone(),
+                source: e,
+            })?;
+            for entry in entries {
+                let path = entry.map_err(|e| Error::Io {
+                    path: e.path().display().to_string(),
+                    source: e.into(),
+                })?;
+                if path.is_file() {
+                    input_paths.push(path.display().to_string());
+                }
+            }
+        } else if Path::new(input).is_dir() {
+            let dir = Path::new(input);
+            for entry in fs::read_dir(dir).map_err(io_error(dir))? {
+                let path = entry.map_err(io_error(dir))?.path();
+                if path.is_file() && has_input_extension(&path) {
+                    input_paths.push(path.display().to_string());
+                }
+            }
+        } else {
+            input_paths.push(input.clone());
+        }
+
+        if input_paths.is_empty() {
+            return Err(Error::NoInputs {
+                input: input.clone(),
+            });
+        }
+        input_paths.sort();
+        for path in input_paths {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
+
+// The name of an input path used in messages.
+pub fn display_name(path: &str) -> &str {
+    if path == STDIN {
+        "<stdin>"
+    } else {
+        path
+    }
+}
+
+// A name identifying an input across runs, e.g. to track which rows were
+// already imported from it. Stdin can't be identified.
+pub fn import_key(path: &str) -> Option<String> {
+    if path == STDIN {
+        return None;
+    }
+    match fs::canonicalize(path) {
+        Ok(path) => Some(path.display().to_string()),
+        Err(_) => Some(path.to_string()),
+    }
+}
+
+// Opens an input file, or stdin, for reading. Compressed gzip and zstd files
+// are detected by their extension or magic bytes and decompressed while
+// reading.
+pub fn open_input(path: &str) -> Result<Box<dyn Read>, Error> {
+    let io_error = |e| Error::Io {
+        path: display_name(path).to_string(),
+        source: e,
+    };
+    let input: Box<dyn Read> = if path == STDIN {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path).map_err(io_error)?)
+    };
+    let mut reader = BufReader::new(input);
+    let magic = reader.fill_buf().map_err(io_error)?;
+
+    if path.ends_with(".gz") || magic.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(flate2::bufread::MultiGzDecoder::new(reader)))
+    } else if path.ends_with(".zst") || magic.starts_with(&ZSTD_MAGIC) {
+        Ok(Box::new(
+            zstd::stream::read::Decoder::with_buffer(reader).map_err(io_error)?,
+        ))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+// Columns of an RBFEvent that are hex encoded and the length their decoded
+// value must have, if any.
+const HEX_COLUMNS: [(&str, Option<usize>); 4] = [
+    ("replaced_txid", Some(32)),
+    ("replaced_raw", None),
+    ("replacement_txid", Some(32)),
+    ("replacement_raw", None),
+];
+
+// The hex serde helpers don't tell us which field they failed on. Find the
+// first hex column that doesn't decode to find out.
+fn find_invalid_hex_column<'a>(get: impl Fn(&str) -> Option<&'a str>) -> Option<RowError> {
+    for (column, length) in HEX_COLUMNS {
+        let Some(value) = get(column) else {
+            continue;
+        };
+        let reason = match hex::decode(value) {
+            Err(e) => e.to_string(),
+            Ok(bytes) if length.is_some_and(|l| l != bytes.len()) => format!(
+                "expected {} bytes, got {}",
+                length.unwrap_or_default(),
+                bytes.len()
+            ),
+            Ok(_) => continue,
+        };
+        return Some(RowError::Hex {
+            column: column.to_string(),
+            reason,
+        });
+    }
+    None
+}
+
+fn deserialize_csv_event(
+    record: &csv::StringRecord,
+    headers: &csv::StringRecord,
+) -> Result<RBFEvent, RowError> {
+    record
+        .deserialize(Some(headers))
+        .map_err(|e| match e.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => {
+                if let Some(column) = err.field().and_then(|i| headers.get(i as usize)) {
+                    return RowError::Field {
+                        column: column.to_string(),
+                        reason: err.kind().to_string(),
+                    };
+                }
+                find_invalid_hex_column(|column| {
+                    let index = headers.iter().position(|h| h == column)?;
+                    record.get(index)
+                })
+                .unwrap_or(RowError::Csv(e))
+            }
+            _ => RowError::Csv(e),
+        })
+}
+
+fn deserialize_json_event(line: &str) -> Result<RBFEvent, RowError> {
+    let value: serde_json::Value = serde_json::from_str(line).map_err(RowError::Json)?;
+    RBFEvent::deserialize(&value).map_err(|e| {
+        find_invalid_hex_column(|column| value.get(column)?.as_str()).unwrap_or(RowError::Json(e))
+    })
+}
+
+type EventRows = Box<dyn Iterator<Item = Result<EventRow, Error>>>;
+
+// CSV files can start with a `# schema_version=N` comment line. Files without
+// it are assumed to use schema version 1. The line is only peeked at here and
+// skipped as a comment by the CSV reader.
+fn peek_schema_version(reader: &mut impl BufRead) -> io::Result<u32> {
+    let buf = reader.fill_buf()?;
+    let first_line = buf.split(|b| *b == b'\n').next().unwrap_or_default();
+    let version = String::from_utf8_lossy(first_line)
+        .strip_prefix('#')
+        .and_then(|c| c.trim().strip_prefix("schema_version="))
+        .and_then(|v| v.trim().parse().ok());
+    Ok(version.unwrap_or(1))
+}
+
+fn read_csv_events(path: &str) -> Result<EventRows, Error> {
+    let name = display_name(path).to_string();
+    let mut reader = BufReader::new(open_input(path)?);
+    let version = peek_schema_version(&mut reader).map_err(|e| Error::Io {
+        path: name.clone(),
+        source: e,
+    })?;
+    if version > SCHEMA_VERSION {
+        return Err(Error::Schema {
+            path: name,
+            version,
+        });
+    }
+    let mut rdr = csv::ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .from_reader(reader);
+    let headers = rdr
+        .headers()
+        .map_err(|e| Error::Csv {
+            path: name.clone(),
+            source: e,
+        })?
+        .clone();
+    let line = |position: Option<&csv::Position>| position.map(|p| p.line()).unwrap_or_default();
+
+    Ok(Box::new(rdr.into_records().map(
+        move |result| match result {
+            Ok(record) => Ok(EventRow {
+                row: line(record.position()),
+                event: deserialize_csv_event(&record, &headers),
+            }),
+            // I/O errors aren't caused by a malformed row and can't be skipped.
+            Err(e) if e.is_io_error() => Err(Error::Csv {
+                path: name.clone(),
+                source: e,
+            }),
+            Err(e) => Ok(EventRow {
+                row: line(e.position()),
+                event: Err(RowError::Csv(e)),
+            }),
+        },
+    )))
+}
+
+fn read_jsonl_events(path: &str) -> Result<EventRows, Error> {
+    let name = display_name(path).to_string();
+    let reader = BufReader::new(open_input(path)?);
+
+    Ok(Box::new((1..).zip(reader.lines()).filter_map(
+        move |(row, line)| match line {
+            Err(e) => Some(Err(Error::Io {
+                path: name.clone(),
+                source: e,
+            })),
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => Some(Ok(EventRow {
+                row,
+                event: deserialize_json_event(&line),
+            })),
+        },
+    )))
+}
+
+// Reads the replacement events from an input. Errors that affect only a
+// single event are returned in its EventRow.
+pub fn read_events(path: &str, format: InputFormat) -> Result<EventRows, Error> {
+    match format {
+        InputFormat::Csv => read_csv_events(path),
+        InputFormat::Jsonl => read_jsonl_events(path),
+    }
+}
+
+// Writes replacement events in one of the input formats to a file or stdout.
+pub enum EventWriter {
+    Csv(String, Box<csv::Writer<Box<dyn Write>>>),
+    Jsonl(String, Box<dyn Write>),
+}
+
+impl EventWriter {
+    pub fn create(path: &str, format: InputFormat) -> Result<EventWriter, Error> {
+        let io_error = |e| Error::Io {
+            path: path.to_string(),
+            source: e,
+        };
+        let mut output: Box<dyn Write> = if path == STDIN {
+            Box::new(BufWriter::new(io::stdout()))
+        } else {
+            Box::new(BufWriter::new(File::create(path).map_err(io_error)?))
+        };
+        match format {
+            InputFormat::Csv => {
+                writeln!(output, "# schema_version={}", SCHEMA_VERSION).map_err(io_error)?;
+                Ok(EventWriter::Csv(
+                    path.to_string(),
+                    Box::new(csv::Writer::from_writer(output)),
+                ))
+            }
+            InputFormat::Jsonl => Ok(EventWriter::Jsonl(path.to_string(), output)),
+        }
+    }
+
+    pub fn write(&mut self, event: &RBFEvent) -> Result<(), Error> {
+        match self {
+            EventWriter::Csv(path, writer) => writer.serialize(event).map_err(|e| Error::Csv {
+                path: path.clone(),
+                source: e,
+            }),
+            EventWriter::Jsonl(path, writer) => {
+                let json = serde_json::to_string(event).map_err(|e| Error::Json {
+                    path: path.clone(),
+                    source: e,
+                })?;
+                writeln!(writer, "{}", json).map_err(|e| Error::Io {
+                    path: path.clone(),
+                    source: e,
+                })
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        let (path, result) = match self {
+            EventWriter::Csv(path, writer) => (path, writer.flush()),
+            EventWriter::Jsonl(path, writer) => (path, writer.flush()),
+        };
+        result.map_err(|e| Error::Io {
+            path: path.clone(),
+            source: e,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct MempoolEntry {
+    #[serde(rename = "bip125-replaceable")]
+    bip125_replaceable: bool,
+}
+
+// Reads a mempool snapshot as returned by Bitcoin Core's `getrawmempool true`
+// and returns the txids of the BIP-125 replaceable transactions in it. Bitcoin
+// Core already accounts for inherited signaling in `bip125-replaceable`.
+pub fn read_mempool_snapshot(path: &str) -> Result<Vec<Txid>, Error> {
+    let name = display_name(path);
+    let mempool: std::collections::HashMap<String, MempoolEntry> =
+        serde_json::from_reader(BufReader::new(open_input(path)?)).map_err(|e| Error::Json {
+            path: name.to_string(),
+            source: e,
+        })?;
+    let mut txids: Vec<Txid> = Vec::new();
+    for (txid, entry) in mempool {
+        if entry.bip125_replaceable {
+            txids.push(Txid::from_str(&txid).map_err(|e| Error::Snapshot {
+                path: name.to_string(),
+                reason: format!("invalid txid '{}': {}", txid, e),
+            })?);
+        }
+    }
+    Ok(txids)
+}
@@ -0,0 +1,33 @@
+use std::sync::atomic::{AtomicI8, Ordering};
+
+// Progress messages are written to stderr, so that the output of commands
+// like `stats` and `query` can be piped. -q hides them, -v shows details.
+static VERBOSITY: AtomicI8 = AtomicI8::new(0);
+
+pub const QUIET: i8 = -1;
+pub const INFO: i8 = 0;
+pub const DEBUG: i8 = 1;
+
+pub fn set_verbosity(verbosity: i8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+pub fn enabled(level: i8) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= level
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::INFO) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::DEBUG) {
+            eprintln!($($arg)*);
+        }
+    };
+}
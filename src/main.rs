@@ -1,24 +1,64 @@
-use std::cmp::min;
-use std::collections::{HashMap, HashSet};
-use std::env;
-use std::fs::File;
-use std::io::Write;
+use std::cmp::{min, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Write};
 use std::process::exit;
 use std::time::SystemTime;
 
+use clap::Parser;
+use rayon::prelude::*;
 use tinytemplate::TinyTemplate;
 
+#[macro_use]
+mod log;
+
+mod adoption;
+mod blocks;
+mod chains;
+mod cli;
+mod error;
+mod groups;
 mod html;
+mod input;
+mod pools;
+mod rbf;
+mod stats;
+mod store;
+#[cfg(test)]
+mod testutil;
 mod types;
+mod views;
 
-use types::RBFEvent;
+use adoption::AdoptionEvents;
+use chains::ReplacementGraph;
+use cli::{Cli, Command, ConvertArgs, Options, QueryArgs, RenderArgs, StatsArgs, ValidateArgs};
+use error::{Error, RowError};
+use groups::NewestReplacementGroups;
+use input::{EventRow, EventWriter, InputFormat};
+use rbf::{ReplacementClass, SignalingIndex, TxSignaling};
+use stats::Statistics;
+use store::Store;
+use types::{RBFEvent, RejectedRow};
+use views::View;
 
 use rawtx_rs::bitcoin;
+use rawtx_rs::bitcoin::hashes::Hash;
 use rawtx_rs::tx::TxInfo;
 use rawtx_rs::{input::InputType, output::OutputType};
 
+// Defaults of --page-size and --max-pages.
 const REPLACEMENT_GROUPS_PER_PAGE: u32 = 100;
 const MAX_PAGES: u32 = 10;
+// Number of the newest replacement groups in the Atom feed.
+const FEED_ENTRIES: usize = 50;
+// Default URL the site is served from, used for links in the Atom feed.
+const SITE_URL: &str = "https://fullrbf.mempool.observer";
+// Number of replacement events decoded and analyzed in parallel at once.
+const EVENT_BATCH_SIZE: usize = 4096;
+// Duplicate events are detected within this many seconds of the newest event
+// read. Events are logged again e.g. when the logger rotates files, so
+// duplicates are close in time.
+const DEDUP_WINDOW: u64 = 24 * 60 * 60;
 
 fn in_and_outputs_to_strings(txinfo: &TxInfo) -> (Vec<String>, Vec<String>) {
     let mut output_type_counts: HashMap<OutputType, u32> = HashMap::new();
@@ -61,15 +101,23 @@ fn build_replacement_context(
     event: &RBFEvent,
     replaced_tx: &bitcoin::Transaction,
     replacement_tx: &bitcoin::Transaction,
-) -> html::ReplacementContext {
-    let replaced_txinfo = TxInfo::new(replaced_tx).unwrap();
-    let replacement_txinfo = TxInfo::new(replacement_tx).unwrap();
+) -> Result<html::ReplacementContext, RowError> {
+    let replaced_txinfo = TxInfo::new(replaced_tx).map_err(|e| RowError::Analysis {
+        column: "replaced_raw",
+        txid: replaced_tx.txid().to_string(),
+        source: e,
+    })?;
+    let replacement_txinfo = TxInfo::new(replacement_tx).map_err(|e| RowError::Analysis {
+        column: "replacement_raw",
+        txid: replacement_tx.txid().to_string(),
+        source: e,
+    })?;
 
     let (replaced_input_infos, repalced_output_infos) = in_and_outputs_to_strings(&replaced_txinfo);
     let (replacement_input_infos, repalcement_output_infos) =
         in_and_outputs_to_strings(&replacement_txinfo);
 
-    html::ReplacementContext {
+    Ok(html::ReplacementContext {
         timestamp: event.timestamp,
         replaced: html::TransactionContext {
             txid: replaced_tx.txid().to_string(),
@@ -87,8 +135,21 @@ fn build_replacement_context(
             op_return: replaced_txinfo.has_opreturn_output(),
             raw: hex::encode(&event.replaced_raw),
             optin_rbf: replaced_txinfo.is_signaling_explicit_rbf_replicability(),
+            truc: rbf::is_truc(replaced_tx),
+            mined: None,
+            mined_checked: false,
             inputs: replaced_input_infos,
             outputs: repalced_output_infos,
+            ancestor_fee: event.replaced_ancestor_fee,
+            ancestor_vsize: event.replaced_ancestor_vsize,
+            ancestor_feerate: match (event.replaced_ancestor_fee, event.replaced_ancestor_vsize) {
+                (Some(fee), Some(vsize)) if vsize > 0 => {
+                    Some(format!("{:.2}", fee as f64 / vsize as f64))
+                }
+                _ => None,
+            },
+            mempool_min_fee: None,
+            peer: None,
         },
         replacement: html::TransactionContext {
             txid: replacement_tx.txid().to_string(),
@@ -101,180 +162,1121 @@ fn build_replacement_context(
             ),
             op_return: replacement_txinfo.has_opreturn_output(),
             optin_rbf: replacement_txinfo.is_signaling_explicit_rbf_replicability(),
+            truc: rbf::is_truc(replacement_tx),
+            mined: None,
+            mined_checked: false,
             raw: hex::encode(&event.replacement_raw),
             inputs: replacement_input_infos,
             outputs: repalcement_output_infos,
+            ancestor_fee: None,
+            ancestor_vsize: None,
+            ancestor_feerate: None,
+            mempool_min_fee: event.mempool_min_fee.map(|f| format!("{:.2}", f)),
+            peer: event.replacement_peer.clone(),
         },
+    })
+}
+
+fn deserialize_tx(
+    raw: &[u8],
+    column: &'static str,
+    txid: String,
+) -> Result<bitcoin::Transaction, RowError> {
+    bitcoin::consensus::encode::deserialize(raw).map_err(|e| RowError::Decode {
+        column,
+        txid,
+        source: e,
+    })
+}
+
+// Checks that the txid logged for a transaction matches the txid of the logged
+// raw transaction. A mismatch indicates that the logger paired the wrong raw
+// transaction with the event.
+fn check_txid(
+    tx: &bitcoin::Transaction,
+    txid: &[u8; 32],
+    column: &'static str,
+) -> Result<(), RowError> {
+    // The txids in the CSV are in the same byte order as the txid hash.
+    if tx.txid().as_inner() != txid {
+        return Err(RowError::TxidMismatch {
+            column,
+            expected: bitcoin::Txid::from_inner(*txid).to_string(),
+            actual: tx.txid().to_string(),
+        });
     }
+    Ok(())
 }
 
-fn conflict(tx1: &bitcoin::Transaction, tx2: &bitcoin::Transaction) -> bool {
-    let tx1_outpoints: HashSet<bitcoin::OutPoint> =
-        tx1.input.iter().map(|i| i.previous_output).collect();
-    let tx2_outpoints: HashSet<bitcoin::OutPoint> =
-        tx2.input.iter().map(|i| i.previous_output).collect();
+// A decoded and analyzed replacement event. It can only be classified once
+// the signaling of the replaced transaction's parents is known.
+struct ProcessedEvent {
+    replaced: TxSignaling,
+    replacement: TxSignaling,
+    // The replaced transaction directly conflicts with the replacement.
+    conflict: bool,
+    context: html::ReplacementContext,
+}
 
-    tx1_outpoints.intersection(&tx2_outpoints).count() > 0
+fn process_event(event: &RBFEvent) -> Result<ProcessedEvent, RowError> {
+    let replaced_tx = deserialize_tx(
+        &event.replaced_raw,
+        "replaced_raw",
+        event.replaced_txid_string(),
+    )?;
+    let replacement_tx = deserialize_tx(
+        &event.replacement_raw,
+        "replacement_raw",
+        event.replacement_txid_string(),
+    )?;
+    check_txid(&replaced_tx, &event.replaced_txid, "replaced_txid")?;
+    check_txid(&replacement_tx, &event.replacement_txid, "replacement_txid")?;
+    Ok(ProcessedEvent {
+        replaced: TxSignaling::new(&replaced_tx),
+        replacement: TxSignaling::new(&replacement_tx),
+        conflict: rbf::conflict(&replaced_tx, &replacement_tx),
+        context: build_replacement_context(event, &replaced_tx, &replacement_tx)?,
+    })
 }
 
-fn get_reverse_fullrbf_replacements(csv_file_path: &str) -> Vec<html::ReplacementContext> {
-    println!("Reading replacements from {}", csv_file_path);
-    let mut rdr = csv::Reader::from_path(csv_file_path).unwrap();
-    let mut replacements: Vec<html::ReplacementContext> = Vec::new();
+// Reads the replacements after `start_row` from an input file, classifies them
+// and passes them to `on_replacement` one by one in the order they were read.
+// In lenient mode, rows that can't be read or analyzed are skipped and
+// returned as RejectedRows instead of failing. The events are decoded and
+// analyzed in batches on the thread pool. Returns the last row read from the
+// input, too.
+//
+// Replacements are classified using the SignalingIndex, to which the
+// transactions of all events are added.
+fn read_replacements(
+    input_path: &str,
+    format: InputFormat,
+    lenient: bool,
+    start_row: u64,
+    pool: &rayon::ThreadPool,
+    index: &mut SignalingIndex,
+    on_replacement: &mut impl FnMut(ReplacementClass, html::ReplacementContext) -> Result<(), Error>,
+) -> Result<(Vec<RejectedRow>, u64), Error> {
+    let name = input::display_name(input_path);
+    info!("Reading replacements from {}", name);
+    let mut counts: BTreeMap<ReplacementClass, u64> = BTreeMap::new();
+    let mut last_row: u64 = start_row;
+    let mut rejected: Vec<RejectedRow> = Vec::new();
+    let mut events = input::read_events(input_path, format)?;
 
-    for csv_replacement in rdr.deserialize() {
-        let event: RBFEvent = csv_replacement.unwrap();
-        let replaced_tx: bitcoin::Transaction =
-            bitcoin::consensus::encode::deserialize(&event.replaced_raw).unwrap();
-        let replacement_tx: bitcoin::Transaction =
-            bitcoin::consensus::encode::deserialize(&event.replacement_raw).unwrap();
-        let optin_rbf = replaced_tx.input.iter().any(|i| i.sequence.is_rbf());
+    loop {
+        let batch = events
+            .by_ref()
+            .filter(|r| r.as_ref().map_or(true, |e| e.row > start_row))
+            .take(EVENT_BATCH_SIZE)
+            .collect::<Result<Vec<EventRow>, Error>>()?;
+        if batch.is_empty() {
+            break;
+        }
+        last_row = batch.iter().map(|e| e.row).max().unwrap_or(last_row);
+        // Collecting an indexed parallel iterator preserves the order.
+        let results: Vec<(u64, Result<ProcessedEvent, RowError>)> = pool.install(|| {
+            batch
+                .into_par_iter()
+                .map(|EventRow { row, event }| (row, event.and_then(|event| process_event(&event))))
+                .collect()
+        });
 
-        // A transaction that did not opt-in to RBF can still be replaced, if it
-        // does not directly conflict with the replacement transaction. These
-        // are not full-RBF replacements though.
-        if !optin_rbf && conflict(&replaced_tx, &replacement_tx) {
-            replacements.push(build_replacement_context(
-                &event,
-                &replaced_tx,
-                &replacement_tx,
-            ))
+        for (row, result) in results {
+            match result {
+                Ok(processed) => {
+                    let class = index.classify(&processed.replaced, processed.conflict);
+                    index.insert(&processed.replaced);
+                    index.insert(&processed.replacement);
+                    *counts.entry(class).or_default() += 1;
+                    on_replacement(class, processed.context)?;
+                }
+                Err(e) if lenient => rejected.push(RejectedRow {
+                    path: name.to_string(),
+                    row,
+                    column: e.column().unwrap_or_default().to_string(),
+                    reason: e.to_string(),
+                }),
+                Err(e) => {
+                    return Err(Error::Row {
+                        path: name.to_string(),
+                        row,
+                        source: e,
+                    })
+                }
+            }
         }
     }
 
-    println!(
-        "Read {} full-rbf replacements from {}",
-        replacements.len(),
-        csv_file_path
+    info!(
+        "Read {} replacements from {} ({})",
+        counts.values().sum::<u64>(),
+        name,
+        format_class_counts(&counts)
     );
-    replacements.reverse();
-    replacements
+    Ok((rejected, last_row))
+}
+
+fn format_class_counts(counts: &BTreeMap<ReplacementClass, u64>) -> String {
+    ReplacementClass::ALL
+        .iter()
+        .map(|c| format!("{} {}", counts.get(c).unwrap_or(&0), c))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn build_thread_pool(options: &Options) -> Result<rayon::ThreadPool, Error> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or_default())
+        .build()
+        .map_err(Error::ThreadPool)
+}
+
+// Remembers the keys of the recently read events to skip duplicates without
+// keeping the keys of all events in memory.
+#[derive(Default)]
+struct RecentEvents {
+    // Timestamp, replaced and replacement txid.
+    keys: BTreeSet<(u64, String, String)>,
+    newest: u64,
+    // Events older than the window when they were read. Their duplicates
+    // can't be detected.
+    outside_window: u64,
+}
+
+impl RecentEvents {
+    // Returns false if the event was already read.
+    fn insert(&mut self, replacement: &html::ReplacementContext) -> bool {
+        let key = (
+            replacement.timestamp,
+            replacement.replaced.txid.clone(),
+            replacement.replacement.txid.clone(),
+        );
+        if key.0 + DEDUP_WINDOW < self.newest {
+            self.outside_window += 1;
+        }
+        if !self.keys.insert(key) {
+            return false;
+        }
+        self.newest = self.newest.max(replacement.timestamp);
+        while let Some(oldest) = self.keys.first() {
+            if oldest.0 + DEDUP_WINDOW >= self.newest {
+                break;
+            }
+            self.keys.pop_first();
+        }
+        true
+    }
+}
+
+// Reads the replacements from all input files. The replacements are streamed
+// to `on_replacement` and not kept in memory here.
+fn read_all_replacements(
+    input_paths: &[String],
+    options: &Options,
+    lenient: bool,
+    mut on_replacement: impl FnMut(ReplacementClass, html::ReplacementContext) -> Result<(), Error>,
+) -> Result<Vec<RejectedRow>, Error> {
+    let pool = build_thread_pool(options)?;
+    let mut index = SignalingIndex::default();
+    load_mempool_snapshot(&mut index, options)?;
+    let mut rejected: Vec<RejectedRow> = Vec::new();
+    // The store ignores duplicates on import, here they are skipped before
+    // they are passed on.
+    let mut recent = RecentEvents::default();
+    let mut on_replacement = |class, replacement: html::ReplacementContext| {
+        if !recent.insert(&replacement) {
+            return Ok(());
+        }
+        on_replacement(class, replacement)
+    };
+    for input_path in input_paths {
+        let format = options
+            .input_format
+            .unwrap_or_else(|| InputFormat::detect(input_path));
+        let (file_rejected, _) = read_replacements(
+            input_path,
+            format,
+            lenient,
+            0,
+            &pool,
+            &mut index,
+            &mut on_replacement,
+        )?;
+        rejected.extend(file_rejected);
+    }
+    if recent.outside_window > 0 {
+        info!(
+            "Warning: {} events were read more than a day after newer events, duplicates of these aren't skipped. Pass the inputs in chronological order or use --store.",
+            recent.outside_window
+        );
+    }
+    Ok(rejected)
+}
+
+fn load_mempool_snapshot(index: &mut SignalingIndex, options: &Options) -> Result<(), Error> {
+    if let Some(path) = &options.mempool_snapshot {
+        let txids = input::read_mempool_snapshot(path)?;
+        info!(
+            "Read {} BIP-125 replaceable transactions from mempool snapshot {}",
+            txids.len(),
+            path
+        );
+        for txid in txids {
+            index.insert_replaceable(txid);
+        }
+    }
+    Ok(())
+}
+
+// Imports the replacements from all input files into the store. Only rows
+// that haven't been imported in a previous run are read.
+fn import_replacements(
+    store: &mut Store,
+    input_paths: &[String],
+    options: &Options,
+    lenient: bool,
+) -> Result<Vec<RejectedRow>, Error> {
+    let pool = build_thread_pool(options)?;
+    let mut index = SignalingIndex::tracking_added();
+    index.load(store.replaceable_txids()?);
+    load_mempool_snapshot(&mut index, options)?;
+    let mut rejected: Vec<RejectedRow> = Vec::new();
+    for input_path in input_paths {
+        let format = options
+            .input_format
+            .unwrap_or_else(|| InputFormat::detect(input_path));
+        let import_key = input::import_key(input_path);
+        let start_row = match &import_key {
+            Some(key) => store.imported_row(key)?,
+            None => 0,
+        };
+        let import = store.begin_import()?;
+        let (file_rejected, last_row) = read_replacements(
+            input_path,
+            format,
+            lenient,
+            start_row,
+            &pool,
+            &mut index,
+            &mut |class, replacement| import.insert(class, &replacement),
+        )?;
+        import.insert_replaceable(&index.take_added())?;
+        import.commit(import_key.as_deref(), last_row)?;
+        rejected.extend(file_rejected);
+    }
+    Ok(rejected)
 }
 
-fn generate_html_files(replacements: Vec<html::ReplacementGroupContext>, html_output_dir: &str) {
-    println!("Generating HTML files to {} ...", html_output_dir);
+fn write_rejected_rows(rejected: &[RejectedRow], html_output_dir: &str) -> Result<(), Error> {
+    create_dir(html_output_dir)?;
+    let filename = format!("{}/rejected.csv", html_output_dir);
+    let csv_error = |e| Error::Csv {
+        path: filename.clone(),
+        source: e,
+    };
+    let mut wtr = csv::Writer::from_path(&filename).map_err(csv_error)?;
+    for row in rejected {
+        wtr.serialize(row).map_err(csv_error)?;
+    }
+    wtr.flush().map_err(|e| Error::Io {
+        path: filename.clone(),
+        source: e,
+    })?;
+    // The summary is part of the output, so it's not hidden by -q.
+    writeln!(
+        io::stdout(),
+        "Skipped {} malformed replacement events (see {})",
+        rejected.len(),
+        filename
+    )
+    .map_err(stdout_error)?;
+    Ok(())
+}
+
+fn template_error(name: &str) -> impl FnOnce(tinytemplate::error::Error) -> Error + '_ {
+    move |e| Error::Template {
+        name: name.to_string(),
+        source: e,
+    }
+}
+
+fn build_templates() -> Result<TinyTemplate<'static>, Error> {
     let mut tt = TinyTemplate::new();
-    tt.add_template("tmpl_transaction", html::TEMPLATE_TX)
-        .unwrap();
-    tt.add_template("tmpl_deltas", html::TEMPLATE_DELTAS)
-        .unwrap();
-    tt.add_template("tmpl_replacement", html::TEMPLATE_REPLACEMENT)
-        .unwrap();
-    tt.add_template("tmpl_navigation", html::TEMPLATE_PAGE_NAVIGATION)
-        .unwrap();
-    tt.add_template("tmpl_site", html::TEMPLATE_SITE).unwrap();
-
-    let timestamp = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+    for (name, template) in [
+        ("tmpl_transaction", html::TEMPLATE_TX),
+        ("tmpl_deltas", html::TEMPLATE_DELTAS),
+        ("tmpl_replacement", html::TEMPLATE_REPLACEMENT),
+        ("tmpl_navigation", html::TEMPLATE_PAGE_NAVIGATION),
+        ("tmpl_header", html::TEMPLATE_HEADER),
+        ("tmpl_script", html::TEMPLATE_SCRIPT),
+        ("tmpl_site", html::TEMPLATE_SITE),
+        ("tmpl_chain_site", html::TEMPLATE_CHAIN_SITE),
+        ("tmpl_tx_site", html::TEMPLATE_TX_SITE),
+        ("tmpl_feed", html::TEMPLATE_FEED),
+        ("tmpl_bar_chart", html::TEMPLATE_BAR_CHART),
+        ("tmpl_stats_site", html::TEMPLATE_STATS_SITE),
+        ("tmpl_adoption_site", html::TEMPLATE_ADOPTION_SITE),
+    ] {
+        tt.add_template(name, template)
+            .map_err(template_error(name))?;
+    }
+    Ok(tt)
+}
+
+fn now() -> u64 {
+    match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
         Ok(n) => n.as_secs(),
         Err(_) => panic!("SystemTime before UNIX EPOCH!"),
+    }
+}
+
+fn write_file(filename: &str, content: &str) -> Result<(), Error> {
+    let io_error = |e| Error::Io {
+        path: filename.to_string(),
+        source: e,
     };
+    let mut file = File::create(filename).map_err(io_error)?;
+    write!(file, "{}", content).map_err(io_error)
+}
+
+fn create_dir(dir: &str) -> Result<(), Error> {
+    fs::create_dir_all(dir).map_err(|e| Error::Io {
+        path: dir.to_string(),
+        source: e,
+    })
+}
+
+// Replacement groups rendered into the `name` subdirectory of the output
+// directory. The root view's name is "".
+struct RenderedView {
+    class: ReplacementClass,
+    name: String,
+    title: String,
+    groups: Vec<html::ReplacementGroupContext>,
+}
 
+fn view_url(name: &str) -> String {
+    if name.is_empty() {
+        String::from("/")
+    } else {
+        format!("/{}/", name)
+    }
+}
+
+// Generates the pages of a view. `views` links all rendered views.
+fn generate_html_files(
+    view: RenderedView,
+    views: &[html::ViewLinkContext],
+    args: &RenderArgs,
+) -> Result<(), Error> {
+    let site_url = args.site_url.trim_end_matches('/');
+    let RenderedView {
+        class,
+        name,
+        title,
+        groups: replacements,
+    } = view;
+    let html_output_dir = &join_path(&args.output_dir, &name);
+    info!("Generating HTML files to {} ...", html_output_dir);
+    create_dir(html_output_dir)?;
+    let tt = build_templates()?;
+    let timestamp = now();
+
+    let outcomes: Vec<html::OutcomeFilterContext> =
+        if replacements.iter().any(|r| r.outcome.is_some()) {
+            blocks::Outcome::ALL
+                .iter()
+                .map(|o| html::OutcomeFilterContext {
+                    name: o.name().to_string(),
+                    title: o.title().to_string(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
     let pages = sequence(min(
-        (replacements.len() as f32 / REPLACEMENT_GROUPS_PER_PAGE as f32) as u32 + 1,
-        MAX_PAGES,
+        (replacements.len() as f32 / args.page_size as f32) as u32 + 1,
+        args.max_pages,
     ));
 
-    for (page, chunk) in (0_u32..).zip(replacements.chunks(REPLACEMENT_GROUPS_PER_PAGE as usize)) {
-        println!("... rendering page {}", page);
+    let mut chunks: Vec<&[html::ReplacementGroupContext]> =
+        replacements.chunks(args.page_size as usize).collect();
+    // Views without replacements still get a page, so links to them work.
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    for (page, chunk) in (0_u32..).zip(chunks) {
+        debug!("... rendering page {}", page);
+        let site = html::SiteContext {
+            replacements: chunk.to_vec(),
+            title: title.clone(),
+            full_rbf: class == ReplacementClass::FullRbf,
+            timestamp,
+            page,
+            navigation: html::NavigationContext {
+                base: view_url(&name),
+                pages: pages.clone(),
+            },
+            views: views
+                .iter()
+                .map(|v| html::ViewLinkContext {
+                    active: v.url == view_url(&name),
+                    ..v.clone()
+                })
+                .collect(),
+            outcomes: outcomes.clone(),
+        };
+        let rendered = tt
+            .render("tmpl_site", &site)
+            .map_err(template_error("tmpl_site"))?;
+
+        let filename = format!("{}/{}.html", html_output_dir, get_filename(page));
+        debug!("... writing page {} to {}", page, filename);
+        write_file(&filename, &rendered)?;
+        write_json(
+            &format!("{}/{}.json", html_output_dir, get_filename(page)),
+            &site,
+        )?;
+    }
+
+    write_json(
+        &format!("{}/latest.json", html_output_dir),
+        &html::LatestContext {
+            title: &title,
+            timestamp,
+            replacements: &replacements,
+        },
+    )?;
+
+    let feed = build_feed(&replacements, &title, &name, site_url, timestamp);
+    let rendered = tt
+        .render("tmpl_feed", &feed)
+        .map_err(template_error("tmpl_feed"))?;
+    write_file(&format!("{}/feed.xml", html_output_dir), &rendered)
+}
+
+fn join_path(dir: &str, path: &str) -> String {
+    if path.is_empty() {
+        dir.to_string()
+    } else {
+        format!("{}/{}", dir, path)
+    }
+}
+
+fn rfc3339(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+// Builds an Atom feed of the newest replacement groups. The entries link to
+// the permalink page of the replacement.
+fn build_feed(
+    replacements: &[html::ReplacementGroupContext],
+    title: &str,
+    view: &str,
+    site_url: &str,
+    timestamp: u64,
+) -> html::FeedContext {
+    let url = join_path(site_url, view);
+    html::FeedContext {
+        title: format!("Recent {}", title),
+        id: format!("{}/", url),
+        url: format!("{}/", url),
+        feed_url: format!("{}/feed.xml", url),
+        updated: rfc3339(
+            replacements
+                .first()
+                .map(|r| r.timestamp)
+                .unwrap_or(timestamp),
+        ),
+        entries: replacements
+            .iter()
+            .take(FEED_ENTRIES)
+            .map(|r| {
+                let url = format!("{}/tx/{}.html", site_url, r.replacement.txid);
+                html::FeedEntryContext {
+                    id: format!("{}#{}", url, r.timestamp),
+                    title: format!(
+                        "{} replaced by {} ({:+} sat)",
+                        r.replaced
+                            .iter()
+                            .map(|tx| tx.txid.as_str())
+                            .collect::<Vec<&str>>()
+                            .join(", "),
+                        r.replacement.txid,
+                        r.delta.fee
+                    ),
+                    url,
+                    updated: rfc3339(r.timestamp),
+                    replaced: r.replaced.iter().map(|tx| tx.txid.clone()).collect(),
+                    replacement: r.replacement.txid.clone(),
+                    fee_delta: r.delta.fee,
+                    timestamp: r.timestamp,
+                }
+            })
+            .collect(),
+    }
+}
+
+fn write_json(filename: &str, value: &impl serde::Serialize) -> Result<(), Error> {
+    let json = serde_json::to_string(value).map_err(|e| Error::Json {
+        path: filename.to_string(),
+        source: e,
+    })?;
+    write_file(filename, &json)
+}
+
+// Adds the stored groups of the rendered transactions that aren't rendered
+// themselves anymore, so that the transaction pages list all their groups.
+fn add_stored_groups(
+    store_path: &str,
+    graph: &ReplacementGraph,
+    tx_groups: &mut BTreeMap<String, Vec<html::ReplacementGroupContext>>,
+    chains: &mut BTreeMap<String, html::ChainContext>,
+) -> Result<(), Error> {
+    let store = Store::open(store_path)?;
+    for (txid, rendered) in tx_groups.iter_mut() {
+        let mut class_groups: BTreeMap<ReplacementClass, NewestReplacementGroups> = BTreeMap::new();
+        store.for_each_replacement_of(txid, |class, replacement| {
+            class_groups
+                .entry(class)
+                .or_insert_with(|| NewestReplacementGroups::new(class, usize::MAX))
+                .insert(&replacement);
+            Ok(())
+        })?;
+        let mut stored: Vec<html::ReplacementGroupContext> = class_groups
+            .into_values()
+            .flat_map(|groups| groups.into_groups())
+            .filter(|group| {
+                !rendered.iter().any(|r| {
+                    r.timestamp == group.timestamp && r.replacement.txid == group.replacement.txid
+                })
+            })
+            .collect();
+        graph.link(&mut stored, chains);
+        rendered.extend(stored);
+    }
+    Ok(())
+}
+
+// Writes a page for each transaction to `tx/<txid>.html`, listing the groups
+// the transaction is part of from newest to oldest. Pages of transactions
+// that are no longer rendered are kept, so links to them keep working as long
+// as the output directory persists.
+fn generate_tx_files(
+    tx_groups: BTreeMap<String, Vec<html::ReplacementGroupContext>>,
+    html_output_dir: &str,
+) -> Result<(), Error> {
+    let dir = format!("{}/tx", html_output_dir);
+    info!(
+        "Generating {} transaction pages to {} ...",
+        tx_groups.len(),
+        dir
+    );
+    create_dir(&dir)?;
+    let tt = build_templates()?;
+    let timestamp = now();
+    for (txid, mut replacements) in tx_groups {
+        replacements.sort_by_key(|r| Reverse(r.timestamp));
+        let filename = format!("{}/{}.html", dir, txid);
         let rendered = tt
             .render(
-                "tmpl_site",
-                &html::SiteContext {
-                    replacements: chunk.to_vec(),
+                "tmpl_tx_site",
+                &html::TxSiteContext {
+                    txid,
+                    replacements,
                     timestamp,
-                    page,
-                    navigation: html::NavigationContext {
-                        pages: pages.clone(),
-                    },
                 },
             )
-            .unwrap();
+            .map_err(template_error("tmpl_tx_site"))?;
+        write_file(&filename, &rendered)?;
+    }
+    Ok(())
+}
 
-        let filename = format!("{}/{}.html", html_output_dir, get_filename(page));
-        println!("... writing page {} to {}", page, filename);
-        let mut file = File::create(filename).unwrap();
-        write!(file, "{}", rendered).unwrap();
-    }
-}
-
-fn build_replacement_groups(
-    replacements: Vec<html::ReplacementContext>,
-) -> Vec<html::ReplacementGroupContext> {
-    let mut replacement_groups: HashMap<
-        (html::TransactionContext, u64),
-        Vec<html::TransactionContext>,
-    > = HashMap::new();
-
-    for replacement_event in replacements.iter() {
-        match replacement_groups.get_mut(&(
-            replacement_event.replacement.clone(),
-            replacement_event.timestamp,
-        )) {
-            None => {
-                replacement_groups.insert(
-                    (
-                        replacement_event.replacement.clone(),
-                        replacement_event.timestamp,
-                    ),
-                    vec![replacement_event.replaced.clone()],
-                );
-            }
-            Some(x) => x.push(replacement_event.replaced.clone()),
+// Writes the statistics page to `stats.html`.
+fn generate_stats_file(
+    statistics: Statistics,
+    class_counts: &BTreeMap<ReplacementClass, u64>,
+    html_output_dir: &str,
+) -> Result<(), Error> {
+    let filename = format!("{}/stats.html", html_output_dir);
+    info!("Generating statistics page {} ...", filename);
+    let tt = build_templates()?;
+    let rendered = tt
+        .render(
+            "tmpl_stats_site",
+            &statistics.into_context(class_counts, now()),
+        )
+        .map_err(template_error("tmpl_stats_site"))?;
+    write_file(&filename, &rendered)
+}
+
+// Writes the per-pool full-RBF adoption report to `pools.html` and
+// `pools.csv`.
+fn generate_adoption_files(
+    events: AdoptionEvents,
+    mined: &blocks::MinedLookup,
+    html_output_dir: &str,
+) -> Result<(), Error> {
+    let report = adoption::build_report(events, mined, now());
+    create_dir(html_output_dir)?;
+    let filename = format!("{}/pools.html", html_output_dir);
+    info!("Generating pool adoption report {} ...", filename);
+    let tt = build_templates()?;
+    let rendered = tt
+        .render("tmpl_adoption_site", &report)
+        .map_err(template_error("tmpl_adoption_site"))?;
+    write_file(&filename, &rendered)?;
+
+    let filename = format!("{}/pools.csv", html_output_dir);
+    let csv_error = |e| Error::Csv {
+        path: filename.clone(),
+        source: e,
+    };
+    let mut wtr = csv::Writer::from_path(&filename).map_err(csv_error)?;
+    for row in adoption::csv_rows(&report) {
+        wtr.serialize(row).map_err(csv_error)?;
+    }
+    wtr.flush().map_err(|e| Error::Io {
+        path: filename.clone(),
+        source: e,
+    })
+}
+
+// Writes a page for each chain to `chains/<tip txid>.html`.
+fn generate_chain_files(
+    chains: BTreeMap<String, html::ChainContext>,
+    html_output_dir: &str,
+) -> Result<(), Error> {
+    let dir = format!("{}/chains", html_output_dir);
+    info!("Generating {} chain pages to {} ...", chains.len(), dir);
+    create_dir(&dir)?;
+    let tt = build_templates()?;
+    let timestamp = now();
+    for (tip, chain) in chains {
+        let rendered = tt
+            .render(
+                "tmpl_chain_site",
+                &html::ChainSiteContext { chain, timestamp },
+            )
+            .map_err(template_error("tmpl_chain_site"))?;
+        write_file(&format!("{}/{}.html", dir, tip), &rendered)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    log::set_verbosity(cli.verbosity());
+    let result = match &cli.command {
+        Command::Render(args) => render(args),
+        Command::Stats(args) => stats(args),
+        Command::Validate(args) => validate(args),
+        Command::Convert(args) => convert(args),
+        Command::Query(args) => query(args),
+    };
+    if let Err(e) = result {
+        // The output was piped into a command that exited early, e.g. `head`.
+        if matches!(&e, Error::Io { source, .. } if source.kind() == io::ErrorKind::BrokenPipe) {
+            return;
+        }
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
+
+fn stdout_error(e: io::Error) -> Error {
+    Error::Io {
+        path: String::from("<stdout>"),
+        source: e,
+    }
+}
+
+// Reads the replacements from the inputs, or imports them into the store and
+// reads all replacements from the store.
+fn read_inputs(
+    inputs: &[String],
+    store: Option<&str>,
+    options: &Options,
+    lenient: bool,
+    mut on_replacement: impl FnMut(ReplacementClass, html::ReplacementContext) -> Result<(), Error>,
+) -> Result<Vec<RejectedRow>, Error> {
+    let input_paths = input::expand_input_paths(inputs)?;
+    match store {
+        None => read_all_replacements(&input_paths, options, lenient, on_replacement),
+        Some(store_path) => {
+            let mut store = Store::open(store_path)?;
+            let rejected = import_replacements(&mut store, &input_paths, options, lenient)?;
+            let count = store.for_each_replacement(&mut on_replacement)?;
+            info!("Read {} replacements from store {}", count, store_path);
+            Ok(rejected)
         }
     }
+}
 
-    let mut replacement_group_contexts: Vec<html::ReplacementGroupContext> = replacement_groups
+// Reads the replacements a second time. Inputs already imported into the
+// store aren't imported again.
+fn reread_inputs(
+    inputs: &[String],
+    store: Option<&str>,
+    options: &Options,
+    lenient: bool,
+    mut on_replacement: impl FnMut(ReplacementClass, html::ReplacementContext) -> Result<(), Error>,
+) -> Result<(), Error> {
+    match store {
+        None => {
+            let input_paths = input::expand_input_paths(inputs)?;
+            read_all_replacements(&input_paths, options, lenient, on_replacement)?;
+        }
+        Some(store_path) => {
+            Store::open(store_path)?.for_each_replacement(&mut on_replacement)?;
+        }
+    }
+    Ok(())
+}
+
+fn render(args: &RenderArgs) -> Result<(), Error> {
+    let html_output_dir = &args.output_dir;
+    let rereadable = args.store.is_some() || !args.inputs.iter().any(|input| input == "-");
+    let filtered_views = View::load(args.views.as_deref(), args.blocks.is_some(), rereadable)?;
+    // Full-RBF replacements are always rendered.
+    let render_classes = args
+        .render_classes
         .iter()
-        .map(|(k, v)| html::ReplacementGroupContext {
-            replaced: v.to_vec(),
-            replacement: k.0.clone(),
-            timestamp: k.1,
-            delta: html::ReplacementGroupDeltaContext {
-                fee: k.0.fee as i64 - v.iter().map(|tx| tx.fee).sum::<u64>() as i64,
-                vsize: k.0.vsize as i64 - v.iter().map(|tx| tx.vsize).sum::<u64>() as i64,
-                feerate: if v.len() > 1 {
-                    String::new()
-                } else {
-                    format!(
-                        "+{:.2} sat/vByte",
-                        (k.0.fee as f64) / (k.0.vsize as f64)
-                            - (v.iter().map(|tx| tx.fee).sum::<u64>() as f64
-                                / v.iter().map(|tx| tx.vsize).sum::<u64>() as f64)
-                    )
-                },
-            },
+        .filter(|c| **c != ReplacementClass::FullRbf);
+
+    // Only the newest groups that fit on the pages are rendered.
+    let limit = (args.max_pages as usize).saturating_mul(args.page_size as usize);
+    let mut replacement_groups = NewestReplacementGroups::new(ReplacementClass::FullRbf, limit);
+    // Views filtering on the mined status are filled in a second pass once
+    // the mined status is known.
+    let mut filtered_view_groups: Vec<Option<NewestReplacementGroups>> = filtered_views
+        .iter()
+        .map(|view| {
+            (!view.filters.needs_mined()).then(|| {
+                NewestReplacementGroups::with_filter(ReplacementClass::FullRbf, limit, |g| {
+                    view.filters.matches(g, None)
+                })
+            })
         })
         .collect();
-    replacement_group_contexts.sort_by_key(|k| k.timestamp);
-    replacement_group_contexts.reverse();
-    replacement_group_contexts
-}
+    let mut class_groups: BTreeMap<ReplacementClass, NewestReplacementGroups> = render_classes
+        .map(|c| (*c, NewestReplacementGroups::new(*c, limit)))
+        .collect();
+    let mut counts: BTreeMap<ReplacementClass, u64> = BTreeMap::new();
+    let mut graph = ReplacementGraph::default();
+    let mut statistics = Statistics::default();
+    let mut adoption_events = AdoptionEvents::default();
+    // The outpoints of the events that views filtering on the mined status
+    // might show, so that a single search of the blocks finds their conflicts.
+    let mut mined_view_outpoints: HashSet<bitcoin::OutPoint> = HashSet::new();
+    let mut on_replacement = |class: ReplacementClass, replacement: html::ReplacementContext| {
+        *counts.entry(class).or_default() += 1;
+        graph.insert(&replacement);
+        if let Some(groups) = class_groups.get_mut(&class) {
+            groups.insert(&replacement);
+        }
+        if class != ReplacementClass::FullRbf {
+            return Ok(());
+        }
+        statistics.insert(&replacement);
+        if args.blocks.is_some() {
+            adoption_events.insert(&replacement);
+        }
+        for (view, groups) in filtered_views.iter().zip(filtered_view_groups.iter_mut()) {
+            if let Some(groups) = groups {
+                if view.filters.matches_event(&replacement) {
+                    groups.insert(&replacement);
+                }
+            }
+        }
+        if filtered_views
+            .iter()
+            .any(|view| view.filters.needs_mined() && view.filters.matches_event(&replacement))
+        {
+            mined_view_outpoints.extend(blocks::event_outpoints(&replacement));
+        }
+        replacement_groups.insert(&replacement);
+        Ok(())
+    };
+    let rejected = read_inputs(
+        &args.inputs,
+        args.store.as_deref(),
+        &args.options,
+        args.lenient,
+        &mut on_replacement,
+    )?;
+    info!("Replacements by class: {}", format_class_counts(&counts));
+    if args.lenient {
+        write_rejected_rows(&rejected, html_output_dir)?;
+    }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        println!("Usage: {} <path/to/*.csv> <html output dir>", args[0]);
-        exit(1);
+    let mut views: Vec<RenderedView> = vec![RenderedView {
+        class: ReplacementClass::FullRbf,
+        name: String::new(),
+        title: ReplacementClass::FullRbf.title().to_string(),
+        groups: replacement_groups.into_groups(),
+    }];
+    for (view, groups) in filtered_views.iter().zip(filtered_view_groups) {
+        views.push(RenderedView {
+            class: ReplacementClass::FullRbf,
+            name: view.name.clone(),
+            title: view.title.clone(),
+            groups: groups.map(|g| g.into_groups()).unwrap_or_default(),
+        });
+    }
+    for (class, groups) in class_groups {
+        views.push(RenderedView {
+            class,
+            name: class.name().to_string(),
+            title: class.title().to_string(),
+            groups: groups.into_groups(),
+        });
     }
 
-    let csv_file_path = &args[1];
-    let html_output_dir = &args[2];
+    if let Some(blocks_dir) = &args.blocks {
+        // The adoption report covers all full-RBF replacements.
+        let mut txids = adoption_events.txids();
+        txids.extend(views.iter().flat_map(|view| blocks::txids(&view.groups)));
+        let pools = pools::Pools::load(args.pools.as_deref())?;
+        let mut outpoints = mined_view_outpoints;
+        outpoints.extend(
+            views
+                .iter()
+                .flat_map(|view| blocks::outpoints(&view.groups)),
+        );
+        let mined = blocks::find_mined(blocks_dir, &txids, &outpoints, &pools)?;
+        for view in views.iter_mut() {
+            blocks::set_mined(&mut view.groups, &mined);
+        }
 
-    let replacements = get_reverse_fullrbf_replacements(csv_file_path);
-    let replacement_group_contexts = build_replacement_groups(replacements);
+        let mut mined_view_groups: Vec<(usize, NewestReplacementGroups)> = filtered_views
+            .iter()
+            .enumerate()
+            .filter(|(_, view)| view.filters.needs_mined())
+            .map(|(i, view)| {
+                let groups =
+                    NewestReplacementGroups::with_filter(ReplacementClass::FullRbf, limit, |g| {
+                        view.filters.matches(g, Some(&mined))
+                    });
+                // The first view is the one of all full-RBF replacements.
+                (i + 1, groups)
+            })
+            .collect();
+        if !mined_view_groups.is_empty() {
+            info!("Reading the replacements again for the views filtering on the mined status");
+            reread_inputs(
+                &args.inputs,
+                args.store.as_deref(),
+                &args.options,
+                args.lenient,
+                |class, replacement| {
+                    if class != ReplacementClass::FullRbf {
+                        return Ok(());
+                    }
+                    for (i, groups) in mined_view_groups.iter_mut() {
+                        if filtered_views[*i - 1].filters.matches_event(&replacement) {
+                            groups.insert(&replacement);
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+            // The lookup already contains all full-RBF transactions and the
+            // spends of the outpoints of these groups.
+            for (i, groups) in mined_view_groups {
+                let mut groups = groups.into_groups();
+                blocks::set_mined(&mut groups, &mined);
+                views[i].groups = groups;
+            }
+        }
+        generate_adoption_files(adoption_events, &mined, html_output_dir)?;
+    }
 
-    let replacement_group_contexts_without_opreturn = replacement_group_contexts
+    let view_links: Vec<html::ViewLinkContext> = views
         .iter()
-        .filter(|r| !r.replacement.op_return)
-        .cloned()
+        .map(|view| html::ViewLinkContext {
+            title: view.title.clone(),
+            url: view_url(&view.name),
+            active: false,
+        })
         .collect();
 
-    generate_html_files(replacement_group_contexts, html_output_dir);
-    generate_html_files(
-        replacement_group_contexts_without_opreturn,
-        &format!("{}/no_opreturn", html_output_dir),
+    // Only chains that rendered replacements are part of, or with a store the
+    // groups on their transaction pages, get a page.
+    let mut chains: BTreeMap<String, html::ChainContext> = BTreeMap::new();
+    let mut tx_groups: BTreeMap<String, Vec<html::ReplacementGroupContext>> = BTreeMap::new();
+    for mut view in views {
+        graph.link(&mut view.groups, &mut chains);
+        groups::index_by_txid(&view.groups, &mut tx_groups);
+        generate_html_files(view, &view_links, args)?;
+    }
+    if let Some(store_path) = &args.store {
+        add_stored_groups(store_path, &graph, &mut tx_groups, &mut chains)?;
+    }
+    generate_stats_file(statistics, &counts, html_output_dir)?;
+    generate_chain_files(chains, html_output_dir)?;
+    generate_tx_files(tx_groups, html_output_dir)?;
+    info!("Done generating pages");
+    Ok(())
+}
+
+// Prints the statistics shown on the statistics page.
+fn stats(args: &StatsArgs) -> Result<(), Error> {
+    let mut counts: BTreeMap<ReplacementClass, u64> = BTreeMap::new();
+    let mut statistics = Statistics::default();
+    read_inputs(
+        &args.inputs,
+        args.store.as_deref(),
+        &args.options,
+        args.lenient,
+        |class, replacement| {
+            *counts.entry(class).or_default() += 1;
+            if class == ReplacementClass::FullRbf {
+                statistics.insert(&replacement);
+            }
+            Ok(())
+        },
+    )?;
+    let stats = statistics.into_context(&counts, now());
+    let mut out = io::stdout().lock();
+    writeln!(out, "Full-RBF replacements: {}", stats.count).map_err(stdout_error)?;
+    if stats.count > 0 {
+        writeln!(
+            out,
+            "With an OP_RETURN output: {} ({}%)",
+            stats.op_return, stats.op_return_share
+        )
+        .map_err(stdout_error)?;
+    }
+    writeln!(out, "Replacement events by class:").map_err(stdout_error)?;
+    for class in &stats.classes {
+        writeln!(out, "  {}: {}", class.name, class.count).map_err(stdout_error)?;
+    }
+    for chart in &stats.charts {
+        writeln!(out, "{}:", chart.title).map_err(stdout_error)?;
+        for bar in &chart.bars {
+            writeln!(out, "  {}", bar.tooltip).map_err(stdout_error)?;
+        }
+    }
+    Ok(())
+}
+
+// Decodes and analyzes all events like `render` and reports the malformed
+// ones. Exits with an error if there are any.
+fn validate(args: &ValidateArgs) -> Result<(), Error> {
+    let input_paths = input::expand_input_paths(&args.inputs)?;
+    let mut count: u64 = 0;
+    let rejected = read_all_replacements(&input_paths, &args.options, true, |_, _| {
+        count += 1;
+        Ok(())
+    })?;
+    let mut out = io::stdout().lock();
+    for row in &rejected {
+        writeln!(out, "{}, row {}: {}", row.path, row.row, row.reason).map_err(stdout_error)?;
+    }
+    writeln!(
+        out,
+        "{} valid and {} malformed replacement events",
+        count,
+        rejected.len()
+    )
+    .map_err(stdout_error)?;
+    if !rejected.is_empty() {
+        return Err(Error::Malformed {
+            count: rejected.len(),
+        });
+    }
+    Ok(())
+}
+
+// Converts the events of all inputs into a single output. The transactions
+// aren't decoded, only rows that can't be read are malformed.
+fn convert(args: &ConvertArgs) -> Result<(), Error> {
+    let input_paths = input::expand_input_paths(&args.inputs)?;
+    let mut writer = EventWriter::create(&args.output, args.to)?;
+    let mut count: u64 = 0;
+    let mut skipped: u64 = 0;
+    for input_path in &input_paths {
+        let format = args
+            .input_format
+            .unwrap_or_else(|| InputFormat::detect(input_path));
+        info!(
+            "Converting replacements from {}",
+            input::display_name(input_path)
+        );
+        for event_row in input::read_events(input_path, format)? {
+            let EventRow { row, event } = event_row?;
+            match event {
+                Ok(event) => {
+                    writer.write(&event)?;
+                    count += 1;
+                }
+                Err(e) if args.lenient => {
+                    debug!("Skipping row {}: {}", row, e);
+                    skipped += 1;
+                }
+                Err(e) => {
+                    return Err(Error::Row {
+                        path: input::display_name(input_path).to_string(),
+                        row,
+                        source: e,
+                    })
+                }
+            }
+        }
+    }
+    writer.flush()?;
+    info!(
+        "Converted {} replacement events, skipped {} malformed ones",
+        count, skipped
     );
-    println!("Done generating pages");
+    Ok(())
+}
+
+// A replacement printed by `query`.
+#[derive(serde::Serialize)]
+struct QueryResult<'a> {
+    class: ReplacementClass,
+    #[serde(flatten)]
+    replacement: &'a html::ReplacementContext,
+}
+
+// Prints the replacements the transactions are part of, either as replaced
+// transaction or as replacement, in the order they were read.
+fn query(args: &QueryArgs) -> Result<(), Error> {
+    let txids: Vec<String> = args.txids.iter().map(|t| t.to_string()).collect();
+    let mut count: u64 = 0;
+    let mut out = io::stdout().lock();
+    read_inputs(
+        &args.inputs,
+        args.store.as_deref(),
+        &args.options,
+        args.lenient,
+        |class, replacement| {
+            if !txids.contains(&replacement.replaced.txid)
+                && !txids.contains(&replacement.replacement.txid)
+            {
+                return Ok(());
+            }
+            let json = serde_json::to_string(&QueryResult {
+                class,
+                replacement: &replacement,
+            })
+            .map_err(|e| Error::Json {
+                path: input::STDIN.to_string(),
+                source: e,
+            })?;
+            writeln!(out, "{}", json).map_err(stdout_error)?;
+            count += 1;
+            Ok(())
+        },
+    )?;
+    info!("Found {} replacements", count);
+    Ok(())
 }
 
 fn sequence(n: u32) -> Vec<u32> {
@@ -288,3 +1290,81 @@ fn get_filename(page: u32) -> String {
         format!("page_{}", page)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The replacement event row shown as sample in the README.
+    const SAMPLE_EVENT: &str = concat!(
+        "1670314778,",
+        "732deec5209fdeee8136053fc67254e580e07dc52415ec28bca792bb0447004c,",
+        "8354,",
+        "110,",
+        "1670310000,",
+        "020000000001011580e7b64d77bee246ac3f241bdb277076e64b737f865eae65454df82f14133f1100000000fdffffff0199d4dc0400000000160014afde86add624371ad0d648387f56865d197e54eb024730440220602149230fbfc4abc265077d014a4eac94f40a46869386bd7305bf145c45e30f022009021d1f0068aba6860ba834f30d1d1f146d4363744351f4ad97ac92602423dd012102cc35398135669fe23e89d67acddb0b9dc227d384d5044cc41a87b932c2456efc00000000,",
+        "ef3f9c361278eb12d5b0fe70911871fa114f969e2c3b96889df8bcc2be384551,",
+        "12469,",
+        "110,",
+        "020000000001011580e7b64d77bee246ac3f241bdb277076e64b737f865eae65454df82f14133f1100000000fdffffff0186c4dc040000000016001416f15047033aff1809b75b39c190b7094af3bea00247304402204604a788f311045f4182609c9a3369f85f6c86a93ba6f2fa1b4ff257c96eec890220390f8a0bd537dc61edc651b295b28370abf93ae56137626d0fd4bc0cb8be99e3012102cc35398135669fe23e89d67acddb0b9dc227d384d5044cc41a87b932c2456efc00000000",
+    );
+
+    fn sample_event() -> Vec<&'static str> {
+        SAMPLE_EVENT.split(',').collect()
+    }
+
+    fn decode(raw: &str) -> bitcoin::Transaction {
+        deserialize_tx(&hex::decode(raw).unwrap(), "raw", String::new()).unwrap()
+    }
+
+    fn txid_bytes(txid: &str) -> [u8; 32] {
+        hex::decode(txid).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn recent_events_skip_duplicates_within_window() {
+        use testutil::replacement;
+        let mut recent = RecentEvents::default();
+        assert!(recent.insert(&replacement(1000, 1, 2)));
+        assert!(!recent.insert(&replacement(1000, 1, 2)));
+        // The same replacement at another time isn't a duplicate.
+        assert!(recent.insert(&replacement(1001, 1, 2)));
+        assert!(recent.insert(&replacement(1000 + DEDUP_WINDOW, 3, 4)));
+        assert!(!recent.insert(&replacement(1000, 1, 2)));
+        assert_eq!(recent.outside_window, 0);
+
+        // Once the window moved on, duplicates can't be detected anymore.
+        assert!(recent.insert(&replacement(1002 + DEDUP_WINDOW, 5, 6)));
+        assert!(recent.insert(&replacement(1000, 1, 2)));
+        assert_eq!(recent.outside_window, 1);
+    }
+
+    #[test]
+    fn check_txid_matches_csv_byte_order() {
+        let event = sample_event();
+        for (txid, raw) in [(event[1], event[5]), (event[6], event[9])] {
+            let tx = decode(raw);
+            assert!(check_txid(&tx, &txid_bytes(txid), "txid").is_ok());
+        }
+    }
+
+    #[test]
+    fn check_txid_rejects_displayed_byte_order() {
+        let event = sample_event();
+        let tx = decode(event[5]);
+        let mut reversed = txid_bytes(event[1]);
+        reversed.reverse();
+        assert_eq!(hex::encode(reversed), tx.txid().to_string());
+        assert!(matches!(
+            check_txid(&tx, &reversed, "replaced_txid"),
+            Err(RowError::TxidMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn check_txid_rejects_other_transaction() {
+        let event = sample_event();
+        let replaced = decode(event[5]);
+        assert!(check_txid(&replaced, &txid_bytes(event[6]), "replaced_txid").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use rawtx_rs::bitcoin::{self, Address, Script};
+use serde::Deserialize;
+
+use crate::error::Error;
+
+// Pool definitions bundled with the binary. They can be replaced with an
+// updated file with --pools.
+static BUNDLED_POOLS: &str = include_str!("../pools.json");
+const BUNDLED_POOLS_NAME: &str = "<bundled pools.json>";
+
+// Other fields, e.g. the `id` and `link` of other pool lists, are ignored, so
+// that these lists can be used with --pools.
+#[derive(Deserialize)]
+struct PoolDefinition {
+    name: String,
+    // Strings pools put into the scriptSig of their coinbase transactions.
+    #[serde(default)]
+    tags: Vec<String>,
+    // Addresses pools pay the coinbase rewards to.
+    #[serde(default)]
+    addresses: Vec<String>,
+}
+
+// Identifies the pool that mined a block.
+pub struct Pools {
+    // Pool names with their coinbase tags.
+    tags: Vec<(String, Vec<String>)>,
+    // Coinbase output scripts to pool names. The scripts of addresses don't
+    // depend on the network, so the definitions work for all networks.
+    scripts: HashMap<Script, String>,
+}
+
+impl Pools {
+    // Loads the pool definitions from a JSON file or the bundled ones.
+    pub fn load(path: Option<&str>) -> Result<Pools, Error> {
+        let (name, json) = match path {
+            Some(path) => (
+                path,
+                std::fs::read_to_string(path).map_err(|e| Error::Io {
+                    path: path.to_string(),
+                    source: e,
+                })?,
+            ),
+            None => (BUNDLED_POOLS_NAME, BUNDLED_POOLS.to_string()),
+        };
+        let definitions: Vec<PoolDefinition> =
+            serde_json::from_str(&json).map_err(|e| Error::Json {
+                path: name.to_string(),
+                source: e,
+            })?;
+
+        let mut scripts: HashMap<Script, String> = HashMap::new();
+        for pool in &definitions {
+            for address in &pool.addresses {
+                let address = Address::from_str(address).map_err(|e| Error::Pools {
+                    path: name.to_string(),
+                    reason: format!("invalid address '{}' of {}: {}", address, pool.name, e),
+                })?;
+                scripts.insert(address.script_pubkey(), pool.name.clone());
+            }
+        }
+        let tags = definitions
+            .into_iter()
+            .map(|pool| (pool.name, pool.tags))
+            .collect();
+        Ok(Pools { tags, scripts })
+    }
+
+    // Identifies the pool by the addresses paid in the coinbase transaction
+    // first, as tags are easier to fake, and by the coinbase tags second.
+    pub fn identify(&self, block: &bitcoin::Block) -> Option<String> {
+        let coinbase = block.txdata.first()?;
+        for output in &coinbase.output {
+            if let Some(name) = self.scripts.get(&output.script_pubkey) {
+                return Some(name.clone());
+            }
+        }
+        let script_sig = String::from_utf8_lossy(coinbase.input.first()?.script_sig.as_bytes());
+        self.tags
+            .iter()
+            .find(|(_, tags)| tags.iter().any(|t| script_sig.contains(t.as_str())))
+            .map(|(name, _)| name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_pools_load() {
+        let pools = Pools::load(None).unwrap();
+        let f2pool = Address::from_str("1KFHE7w8BhaENAswwryaoccDb6qcT6DbYY").unwrap();
+        assert_eq!(
+            pools
+                .scripts
+                .get(&f2pool.script_pubkey())
+                .map(String::as_str),
+            Some("F2Pool")
+        );
+    }
+}
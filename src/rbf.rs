@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use rawtx_rs::bitcoin::{self, Txid};
+use serde::{Deserialize, Serialize};
+
+// The reason a transaction could be replaced.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplacementClass {
+    // The replaced transaction doesn't signal replaceability and directly
+    // conflicts with the replacement.
+    FullRbf,
+    // The replaced transaction signals BIP-125 replaceability itself.
+    OptIn,
+    // The replaced transaction doesn't conflict with the replacement. It was
+    // evicted as a descendant of a replaced transaction.
+    DescendantEviction,
+    // The replaced transaction inherits BIP-125 replaceability from a parent.
+    InheritedSignaling,
+    // The replaced transaction is a TRUC (version 3) transaction, which is
+    // always replaceable.
+    Truc,
+}
+
+impl ReplacementClass {
+    pub const ALL: [ReplacementClass; 5] = [
+        ReplacementClass::FullRbf,
+        ReplacementClass::OptIn,
+        ReplacementClass::DescendantEviction,
+        ReplacementClass::InheritedSignaling,
+        ReplacementClass::Truc,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReplacementClass::FullRbf => "full-rbf",
+            ReplacementClass::OptIn => "opt-in",
+            ReplacementClass::DescendantEviction => "descendant-eviction",
+            ReplacementClass::InheritedSignaling => "inherited-signaling",
+            ReplacementClass::Truc => "truc",
+        }
+    }
+
+    // Used in page titles, e.g. "Recent {title}".
+    pub fn title(&self) -> &'static str {
+        match self {
+            ReplacementClass::FullRbf => "full-RBF replacements",
+            ReplacementClass::OptIn => "opt-in RBF replacements",
+            ReplacementClass::DescendantEviction => "descendant evictions",
+            ReplacementClass::InheritedSignaling => "replacements with inherited signaling",
+            ReplacementClass::Truc => "TRUC replacements",
+        }
+    }
+
+    // Shown on each replacement, e.g. "full-RBF event".
+    pub fn event(&self) -> &'static str {
+        match self {
+            ReplacementClass::FullRbf => "full-RBF event",
+            ReplacementClass::OptIn => "opt-in RBF event",
+            ReplacementClass::DescendantEviction => "descendant eviction",
+            ReplacementClass::InheritedSignaling => "inherited signaling event",
+            ReplacementClass::Truc => "TRUC event",
+        }
+    }
+}
+
+impl fmt::Display for ReplacementClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ReplacementClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ReplacementClass::ALL
+            .into_iter()
+            .find(|c| c.name() == s)
+            .ok_or_else(|| format!("unknown replacement class '{}'", s))
+    }
+}
+
+pub fn conflict(tx1: &bitcoin::Transaction, tx2: &bitcoin::Transaction) -> bool {
+    let tx1_outpoints: HashSet<bitcoin::OutPoint> =
+        tx1.input.iter().map(|i| i.previous_output).collect();
+    let tx2_outpoints: HashSet<bitcoin::OutPoint> =
+        tx2.input.iter().map(|i| i.previous_output).collect();
+
+    tx1_outpoints.intersection(&tx2_outpoints).count() > 0
+}
+
+// Transactions with this version opt into the TRUC (topologically restricted
+// until confirmation) policy and are always replaceable.
+const TRUC_VERSION: i32 = 3;
+
+pub fn is_truc(tx: &bitcoin::Transaction) -> bool {
+    tx.version == TRUC_VERSION
+}
+
+// The BIP-125 signaling of a transaction and the transactions it spends from.
+pub struct TxSignaling {
+    pub txid: Txid,
+    // The transaction itself signals replaceability.
+    pub explicit: bool,
+    // The transaction is a TRUC (version 3) transaction.
+    pub truc: bool,
+    pub parents: Vec<Txid>,
+}
+
+impl TxSignaling {
+    pub fn new(tx: &bitcoin::Transaction) -> Self {
+        let mut parents: Vec<Txid> = tx.input.iter().map(|i| i.previous_output.txid).collect();
+        parents.sort();
+        parents.dedup();
+        TxSignaling {
+            txid: tx.txid(),
+            explicit: tx.input.iter().any(|i| i.sequence.is_rbf()),
+            truc: is_truc(tx),
+            parents,
+        }
+    }
+}
+
+// Tracks the transactions known to be BIP-125 replaceable. A transaction is
+// replaceable if it signals replaceability itself or if it inherits it from an
+// unconfirmed, replaceable parent. We only know about transactions seen in
+// replacement events or in a mempool snapshot and assume that these parents
+// were still unconfirmed when their children were replaced. Parents have to
+// be inserted before their children, i.e. the events are read in order.
+#[derive(Default)]
+pub struct SignalingIndex {
+    replaceable: HashSet<Txid>,
+    // Replaceable transactions added since the last call to take_added().
+    // Only tracked when importing into a store, which persists them.
+    added: Option<Vec<Txid>>,
+}
+
+impl SignalingIndex {
+    pub fn tracking_added() -> Self {
+        SignalingIndex {
+            added: Some(Vec::new()),
+            ..SignalingIndex::default()
+        }
+    }
+
+    // Returns true if the transaction doesn't signal replaceability itself,
+    // but a known parent of it is replaceable.
+    pub fn inherits_signaling(&self, tx: &TxSignaling) -> bool {
+        !tx.explicit && tx.parents.iter().any(|p| self.replaceable.contains(p))
+    }
+
+    // Classifies the replacement of a transaction. `conflict` is true if the
+    // replaced transaction directly conflicts with the replacement.
+    pub fn classify(&self, replaced: &TxSignaling, conflict: bool) -> ReplacementClass {
+        if !conflict {
+            ReplacementClass::DescendantEviction
+        } else if replaced.truc {
+            ReplacementClass::Truc
+        } else if replaced.explicit {
+            ReplacementClass::OptIn
+        } else if self.inherits_signaling(replaced) {
+            ReplacementClass::InheritedSignaling
+        } else {
+            ReplacementClass::FullRbf
+        }
+    }
+
+    pub fn insert(&mut self, tx: &TxSignaling) {
+        if tx.explicit || tx.truc || self.inherits_signaling(tx) {
+            self.insert_replaceable(tx.txid);
+        }
+    }
+
+    pub fn insert_replaceable(&mut self, txid: Txid) {
+        if self.replaceable.insert(txid) {
+            if let Some(added) = &mut self.added {
+                added.push(txid);
+            }
+        }
+    }
+
+    // Adds replaceable transactions that aren't returned by take_added(),
+    // e.g. because they were loaded from the store.
+    pub fn load(&mut self, txids: impl IntoIterator<Item = Txid>) {
+        self.replaceable.extend(txids);
+    }
+
+    pub fn take_added(&mut self) -> Vec<Txid> {
+        self.added.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::txid;
+
+    fn tx(n: u8, explicit: bool, truc: bool, parents: &[u8]) -> TxSignaling {
+        TxSignaling {
+            txid: txid(n),
+            explicit,
+            truc,
+            parents: parents.iter().map(|p| txid(*p)).collect(),
+        }
+    }
+
+    #[test]
+    fn classify_by_own_signaling() {
+        let index = SignalingIndex::default();
+        assert_eq!(
+            index.classify(&tx(1, false, false, &[]), true),
+            ReplacementClass::FullRbf
+        );
+        assert_eq!(
+            index.classify(&tx(1, true, false, &[]), true),
+            ReplacementClass::OptIn
+        );
+        assert_eq!(
+            index.classify(&tx(1, true, false, &[]), false),
+            ReplacementClass::DescendantEviction
+        );
+    }
+
+    #[test]
+    fn classify_truc_before_other_signaling() {
+        let mut index = SignalingIndex::default();
+        index.insert(&tx(1, true, false, &[]));
+        assert_eq!(
+            index.classify(&tx(2, true, true, &[]), true),
+            ReplacementClass::Truc
+        );
+        assert_eq!(
+            index.classify(&tx(2, false, true, &[1]), true),
+            ReplacementClass::Truc
+        );
+        assert_eq!(
+            index.classify(&tx(2, false, true, &[1]), false),
+            ReplacementClass::DescendantEviction
+        );
+    }
+
+    #[test]
+    fn classify_inherited_signaling() {
+        let mut index = SignalingIndex::default();
+        // A replaceable parent, a child inheriting from it and a grandchild
+        // inheriting from the child.
+        index.insert(&tx(1, true, false, &[]));
+        index.insert(&tx(2, false, false, &[1]));
+        assert_eq!(
+            index.classify(&tx(2, false, false, &[1]), true),
+            ReplacementClass::InheritedSignaling
+        );
+        assert_eq!(
+            index.classify(&tx(3, false, false, &[9, 2]), true),
+            ReplacementClass::InheritedSignaling
+        );
+        // TRUC parents are replaceable, too.
+        index.insert(&tx(4, false, true, &[]));
+        assert_eq!(
+            index.classify(&tx(5, false, false, &[4]), true),
+            ReplacementClass::InheritedSignaling
+        );
+    }
+
+    #[test]
+    fn classify_without_replaceable_parents() {
+        let mut index = SignalingIndex::default();
+        index.insert(&tx(1, false, false, &[]));
+        assert_eq!(
+            index.classify(&tx(2, false, false, &[1]), true),
+            ReplacementClass::FullRbf
+        );
+        // A parent read after its child isn't known when the child is
+        // classified.
+        let child = tx(4, false, false, &[3]);
+        assert_eq!(index.classify(&child, true), ReplacementClass::FullRbf);
+        index.insert(&tx(3, true, false, &[]));
+        assert_eq!(
+            index.classify(&child, true),
+            ReplacementClass::InheritedSignaling
+        );
+    }
+
+    #[test]
+    fn added_only_tracked_when_requested() {
+        let mut index = SignalingIndex::default();
+        index.insert(&tx(1, true, false, &[]));
+        assert!(index.take_added().is_empty());
+
+        let mut index = SignalingIndex::tracking_added();
+        index.load([txid(1)]);
+        index.insert(&tx(1, true, false, &[]));
+        index.insert(&tx(2, false, false, &[1]));
+        index.insert(&tx(3, false, false, &[]));
+        assert_eq!(index.take_added(), vec![txid(2)]);
+        assert!(index.take_added().is_empty());
+    }
+}
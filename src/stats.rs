@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Timelike;
+
+use crate::html;
+use crate::rbf::ReplacementClass;
+
+// Chart dimensions in pixels.
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 240.0;
+// Space below the bars for the labels.
+const LABEL_HEIGHT: f64 = 20.0;
+// Maximum number of bar labels shown per chart. Only every n-th bar is
+// labeled on charts with more bars.
+const MAX_LABELS: usize = 16;
+
+// Upper bounds (exclusive) of the histogram buckets. Values larger than the
+// last bound go into an extra bucket.
+const FEE_DELTA_BOUNDS: [i64; 6] = [0, 100, 1_000, 10_000, 100_000, 1_000_000];
+const FEERATE_DELTA_BOUNDS: [f64; 8] = [0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0];
+const TIME_IN_MEMPOOL_BOUNDS: [i64; 7] = [
+    60,
+    10 * 60,
+    60 * 60,
+    6 * 60 * 60,
+    24 * 60 * 60,
+    3 * 24 * 60 * 60,
+    14 * 24 * 60 * 60,
+];
+const TIME_IN_MEMPOOL_LABELS: [&str; 7] = ["1m", "10m", "1h", "6h", "1d", "3d", "14d"];
+
+// Aggregates statistics over all full-RBF replacements.
+#[derive(Default)]
+pub struct Statistics {
+    count: u64,
+    op_return: u64,
+    per_day: BTreeMap<String, u64>,
+    per_hour: [u64; 24],
+    fee_delta: [u64; FEE_DELTA_BOUNDS.len() + 1],
+    feerate_delta: [u64; FEERATE_DELTA_BOUNDS.len() + 1],
+    time_in_mempool: [u64; TIME_IN_MEMPOOL_BOUNDS.len() + 1],
+    input_types: HashMap<String, u64>,
+    output_types: HashMap<String, u64>,
+}
+
+fn bucket<T: PartialOrd>(bounds: &[T], value: T) -> usize {
+    bounds
+        .iter()
+        .position(|b| value < *b)
+        .unwrap_or(bounds.len())
+}
+
+// Counts the types in strings like "2x P2WPKH" as built by
+// in_and_outputs_to_strings().
+fn count_types(types: &mut HashMap<String, u64>, strings: &[String]) {
+    for s in strings {
+        if let Some((count, t)) = s.split_once("x ") {
+            *types.entry(t.to_string()).or_default() += count.parse::<u64>().unwrap_or(1);
+        }
+    }
+}
+
+impl Statistics {
+    pub fn insert(&mut self, replacement: &html::ReplacementContext) {
+        let replaced = &replacement.replaced;
+        let new = &replacement.replacement;
+        self.count += 1;
+        if new.op_return {
+            self.op_return += 1;
+        }
+        if let Some(time) = chrono::DateTime::from_timestamp(replacement.timestamp as i64, 0) {
+            *self
+                .per_day
+                .entry(time.format("%Y-%m-%d").to_string())
+                .or_default() += 1;
+            self.per_hour[time.hour() as usize] += 1;
+        }
+
+        let fee_delta = new.fee as i64 - replaced.fee as i64;
+        self.fee_delta[bucket(&FEE_DELTA_BOUNDS, fee_delta)] += 1;
+        let feerate_delta =
+            new.fee as f64 / new.vsize as f64 - replaced.fee as f64 / replaced.vsize as f64;
+        self.feerate_delta[bucket(&FEERATE_DELTA_BOUNDS, feerate_delta)] += 1;
+        // The entry time of the replaced transaction isn't always known.
+        if replaced.time_in_mempool > 0 {
+            self.time_in_mempool[bucket(&TIME_IN_MEMPOOL_BOUNDS, replaced.time_in_mempool)] += 1;
+        }
+
+        count_types(&mut self.input_types, &new.inputs);
+        count_types(&mut self.output_types, &new.outputs);
+    }
+
+    pub fn into_context(
+        self,
+        class_counts: &BTreeMap<ReplacementClass, u64>,
+        timestamp: u64,
+    ) -> html::StatsSiteContext {
+        let fee_delta_labels = bucket_labels(&FEE_DELTA_BOUNDS.map(|b| b.to_string()));
+        let feerate_delta_labels = bucket_labels(&FEERATE_DELTA_BOUNDS.map(|b| b.to_string()));
+        let time_in_mempool_labels = bucket_labels(&TIME_IN_MEMPOOL_LABELS.map(|l| l.to_string()));
+
+        html::StatsSiteContext {
+            timestamp,
+            count: self.count,
+            op_return: self.op_return,
+            op_return_share: if self.count > 0 {
+                format!("{:.1}", self.op_return as f64 * 100.0 / self.count as f64)
+            } else {
+                String::new()
+            },
+            classes: ReplacementClass::ALL
+                .iter()
+                .map(|c| html::ClassCountContext {
+                    name: c.name().to_string(),
+                    title: c.title().to_string(),
+                    count: class_counts.get(c).copied().unwrap_or_default(),
+                })
+                .collect(),
+            charts: vec![
+                bar_chart(
+                    "Full-RBF replacements per day (UTC)",
+                    self.per_day.into_iter().collect(),
+                ),
+                bar_chart(
+                    "Full-RBF replacements per hour of the day (UTC)",
+                    (0..)
+                        .zip(self.per_hour)
+                        .map(|(h, c)| (format!("{:02}", h), c))
+                        .collect(),
+                ),
+                bar_chart(
+                    "Fee delta (sat)",
+                    fee_delta_labels.into_iter().zip(self.fee_delta).collect(),
+                ),
+                bar_chart(
+                    "Feerate delta (sat/vByte)",
+                    feerate_delta_labels
+                        .into_iter()
+                        .zip(self.feerate_delta)
+                        .collect(),
+                ),
+                bar_chart(
+                    "Time the replaced transaction spent in the mempool",
+                    time_in_mempool_labels
+                        .into_iter()
+                        .zip(self.time_in_mempool)
+                        .collect(),
+                ),
+                bar_chart(
+                    "Input types of the replacements",
+                    sorted_by_count(self.input_types),
+                ),
+                bar_chart(
+                    "Output types of the replacements",
+                    sorted_by_count(self.output_types),
+                ),
+            ],
+        }
+    }
+}
+
+// Labels for the buckets defined by the bounds, e.g. "<0", "0-100" and ">=100".
+fn bucket_labels(bounds: &[String]) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for (i, bound) in bounds.iter().enumerate() {
+        labels.push(match i {
+            0 => format!("<{}", bound),
+            _ => format!("{}-{}", bounds[i - 1], bound),
+        });
+    }
+    if let Some(last) = bounds.last() {
+        labels.push(format!(">={}", last));
+    }
+    labels
+}
+
+fn sorted_by_count(types: HashMap<String, u64>) -> Vec<(String, u64)> {
+    let mut types: Vec<(String, u64)> = types.into_iter().collect();
+    types.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    types
+}
+
+// Lays out the bars of an SVG bar chart. The chart is rendered by the
+// tmpl_bar_chart template.
+fn bar_chart(title: &str, bars: Vec<(String, u64)>) -> html::BarChartContext {
+    let max = bars
+        .iter()
+        .map(|(_, v)| *v)
+        .max()
+        .unwrap_or_default()
+        .max(1);
+    let slot = CHART_WIDTH / bars.len().max(1) as f64;
+    let label_every = bars.len().div_ceil(MAX_LABELS).max(1);
+    let plot_height = CHART_HEIGHT - LABEL_HEIGHT;
+    html::BarChartContext {
+        title: title.to_string(),
+        width: CHART_WIDTH,
+        height: CHART_HEIGHT,
+        label_y: CHART_HEIGHT - 5.0,
+        max,
+        bars: bars
+            .into_iter()
+            .enumerate()
+            .map(|(i, (label, value))| {
+                let height = plot_height * value as f64 / max as f64;
+                html::BarContext {
+                    x: format!("{:.1}", i as f64 * slot + slot * 0.1),
+                    y: format!("{:.1}", plot_height - height),
+                    width: format!("{:.1}", slot * 0.8),
+                    height: format!("{:.1}", height),
+                    label_x: format!("{:.1}", i as f64 * slot + slot / 2.0),
+                    label: if i % label_every == 0 {
+                        label.clone()
+                    } else {
+                        String::new()
+                    },
+                    tooltip: format!("{}: {}", label, value),
+                }
+            })
+            .collect(),
+    }
+}
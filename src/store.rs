@@ -0,0 +1,263 @@
+use std::str::FromStr;
+
+use rawtx_rs::bitcoin::Txid;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::error::Error;
+use crate::html;
+use crate::rbf::ReplacementClass;
+
+// Replacements of all classes are stored with their precomputed contexts, so they
+// don't have to be decoded and analyzed again on every run. For each input
+// file, the last imported row is tracked and only newer rows are imported on
+// the next run. This assumes input files are only appended to.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS replacements (
+    timestamp INTEGER NOT NULL,
+    replaced_txid TEXT NOT NULL,
+    replacement_txid TEXT NOT NULL,
+    replaced TEXT NOT NULL,
+    replacement TEXT NOT NULL,
+    class TEXT NOT NULL DEFAULT 'full-rbf',
+    PRIMARY KEY (timestamp, replaced_txid, replacement_txid)
+);
+CREATE INDEX IF NOT EXISTS replacements_replaced ON replacements (replaced_txid);
+CREATE INDEX IF NOT EXISTS replacements_replacement ON replacements (replacement_txid);
+CREATE TABLE IF NOT EXISTS imports (
+    input TEXT PRIMARY KEY,
+    row INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS replaceable (
+    txid TEXT PRIMARY KEY
+);
+";
+
+pub struct Store {
+    path: String,
+    conn: Connection,
+}
+
+// An import of replacements from a single input into the store. Nothing is
+// written to the store until the import is committed.
+pub struct Import<'a> {
+    path: &'a str,
+    tx: Transaction<'a>,
+}
+
+impl Store {
+    pub fn open(path: &str) -> Result<Store, Error> {
+        let store_error = |e| Error::Store {
+            path: path.to_string(),
+            source: e,
+        };
+        let conn = Connection::open(path).map_err(store_error)?;
+        conn.execute_batch(SCHEMA).map_err(store_error)?;
+        // Stores created before replacements were classified only contain
+        // full-RBF replacements.
+        if conn
+            .prepare("SELECT class FROM replacements LIMIT 0")
+            .is_err()
+        {
+            conn.execute_batch(
+                "ALTER TABLE replacements ADD COLUMN class TEXT NOT NULL DEFAULT 'full-rbf'",
+            )
+            .map_err(store_error)?;
+        }
+        Ok(Store {
+            path: path.to_string(),
+            conn,
+        })
+    }
+
+    fn store_error(&self) -> impl FnOnce(rusqlite::Error) -> Error + '_ {
+        move |e| Error::Store {
+            path: self.path.clone(),
+            source: e,
+        }
+    }
+
+    // Returns the last row imported from the input or 0 if nothing has been
+    // imported from it yet.
+    pub fn imported_row(&self, input: &str) -> Result<u64, Error> {
+        let row: Option<u64> = self
+            .conn
+            .query_row(
+                "SELECT row FROM imports WHERE input = ?1",
+                params![input],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(self.store_error())?;
+        Ok(row.unwrap_or_default())
+    }
+
+    // Returns the txids of all transactions known to be BIP-125 replaceable.
+    pub fn replaceable_txids(&self) -> Result<Vec<Txid>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT txid FROM replaceable")
+            .map_err(self.store_error())?;
+        let txids = stmt
+            .query_map([], |r| r.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+            .map_err(self.store_error())?;
+        // Txids that can't be parsed were not written by us and are ignored.
+        Ok(txids
+            .iter()
+            .filter_map(|txid| Txid::from_str(txid).ok())
+            .collect())
+    }
+
+    pub fn begin_import(&mut self) -> Result<Import<'_>, Error> {
+        let path = &self.path;
+        let tx = self.conn.transaction().map_err(|e| Error::Store {
+            path: path.clone(),
+            source: e,
+        })?;
+        Ok(Import { path, tx })
+    }
+
+    // Passes all stored replacements to `on_replacement` ordered from oldest
+    // to newest and returns the number of replacements.
+    pub fn for_each_replacement(
+        &self,
+        on_replacement: impl FnMut(ReplacementClass, html::ReplacementContext) -> Result<(), Error>,
+    ) -> Result<u64, Error> {
+        self.query_replacements(
+            "SELECT timestamp, replaced, replacement, class FROM replacements ORDER BY timestamp",
+            [],
+            on_replacement,
+        )
+    }
+
+    // Passes the stored replacement groups the transaction is part of, with
+    // all events of these groups, to `on_replacement`.
+    pub fn for_each_replacement_of(
+        &self,
+        txid: &str,
+        on_replacement: impl FnMut(ReplacementClass, html::ReplacementContext) -> Result<(), Error>,
+    ) -> Result<u64, Error> {
+        self.query_replacements(
+            "SELECT r.timestamp, r.replaced, r.replacement, r.class FROM replacements r
+                JOIN (
+                    SELECT DISTINCT timestamp, replacement_txid FROM replacements
+                        WHERE replaced_txid = ?1 OR replacement_txid = ?1
+                ) g ON r.timestamp = g.timestamp AND r.replacement_txid = g.replacement_txid
+                ORDER BY r.timestamp",
+            params![txid],
+            on_replacement,
+        )
+    }
+
+    fn query_replacements(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+        mut on_replacement: impl FnMut(ReplacementClass, html::ReplacementContext) -> Result<(), Error>,
+    ) -> Result<u64, Error> {
+        let mut stmt = self.conn.prepare(sql).map_err(self.store_error())?;
+        let mut rows = stmt.query(params).map_err(self.store_error())?;
+        let mut count: u64 = 0;
+        while let Some(row) = rows.next().map_err(self.store_error())? {
+            let timestamp: u64 = row.get(0).map_err(self.store_error())?;
+            let replaced: String = row.get(1).map_err(self.store_error())?;
+            let replacement: String = row.get(2).map_err(self.store_error())?;
+            let class: ReplacementClass = row.get(3).map_err(self.store_error())?;
+            let json_error = |e| Error::Json {
+                path: self.path.clone(),
+                source: e,
+            };
+            on_replacement(
+                class,
+                html::ReplacementContext {
+                    timestamp,
+                    replaced: serde_json::from_str(&replaced).map_err(json_error)?,
+                    replacement: serde_json::from_str(&replacement).map_err(json_error)?,
+                },
+            )?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl Import<'_> {
+    fn store_error(&self) -> impl FnOnce(rusqlite::Error) -> Error + '_ {
+        move |e| Error::Store {
+            path: self.path.to_string(),
+            source: e,
+        }
+    }
+
+    // Inserts a replacement. Replacements that are already stored are ignored.
+    pub fn insert(
+        &self,
+        class: ReplacementClass,
+        replacement: &html::ReplacementContext,
+    ) -> Result<(), Error> {
+        let json_error = |e| Error::Json {
+            path: self.path.to_string(),
+            source: e,
+        };
+        let replaced = serde_json::to_string(&replacement.replaced).map_err(json_error)?;
+        let replacement_json =
+            serde_json::to_string(&replacement.replacement).map_err(json_error)?;
+        self.tx
+            .prepare_cached(
+                "INSERT OR IGNORE INTO replacements
+                    (timestamp, replaced_txid, replacement_txid, replaced, replacement, class)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
+                    replacement.timestamp,
+                    replacement.replaced.txid,
+                    replacement.replacement.txid,
+                    replaced,
+                    replacement_json,
+                    class.name(),
+                ])
+            })
+            .map_err(self.store_error())?;
+        Ok(())
+    }
+
+    pub fn insert_replaceable(&self, txids: &[Txid]) -> Result<(), Error> {
+        let mut stmt = self
+            .tx
+            .prepare_cached("INSERT OR IGNORE INTO replaceable (txid) VALUES (?1)")
+            .map_err(self.store_error())?;
+        for txid in txids {
+            stmt.execute(params![txid.to_string()])
+                .map_err(self.store_error())?;
+        }
+        Ok(())
+    }
+
+    // Records the last row imported from the input, if it is a file that can
+    // be read again, and commits the import.
+    pub fn commit(self, input: Option<&str>, row: u64) -> Result<(), Error> {
+        if let Some(input) = input {
+            self.tx
+                .execute(
+                    "INSERT OR REPLACE INTO imports (input, row) VALUES (?1, ?2)",
+                    params![input, row],
+                )
+                .map_err(self.store_error())?;
+        }
+        let path = self.path.to_string();
+        self.tx
+            .commit()
+            .map_err(|e| Error::Store { path, source: e })
+    }
+}
+
+impl FromSql for ReplacementClass {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
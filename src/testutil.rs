@@ -0,0 +1,46 @@
+use rawtx_rs::bitcoin::hashes::Hash;
+use rawtx_rs::bitcoin::Txid;
+
+use crate::html;
+
+// Fixtures shared by the unit tests.
+
+// A txid made of the byte `n`, so that tests can refer to transactions by
+// number.
+pub fn txid(n: u8) -> Txid {
+    Txid::from_inner([n; 32])
+}
+
+// A transaction with txid(n) and a vsize of 100 vByte.
+pub fn tx(n: u8, fee: u64) -> html::TransactionContext {
+    html::TransactionContext {
+        txid: txid(n).to_string(),
+        fee,
+        vsize: 100,
+        feerate: String::new(),
+        raw: String::new(),
+        time_in_mempool: 0,
+        inputs: vec![],
+        outputs: vec![],
+        op_return: false,
+        optin_rbf: false,
+        truc: false,
+        mined: None,
+        mined_checked: false,
+        ancestor_fee: None,
+        ancestor_vsize: None,
+        ancestor_feerate: None,
+        mempool_min_fee: None,
+        peer: None,
+    }
+}
+
+// The replacement of transaction `replaced` by `replacement`, with fees of
+// 1000 sat per txid number.
+pub fn replacement(timestamp: u64, replaced: u8, replacement: u8) -> html::ReplacementContext {
+    html::ReplacementContext {
+        timestamp,
+        replaced: tx(replaced, replaced as u64 * 1000),
+        replacement: tx(replacement, replacement as u64 * 1000),
+    }
+}
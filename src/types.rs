@@ -4,6 +4,11 @@ use serde::{Deserialize, Serialize};
 
 const TXID_LENGTH: usize = 32;
 
+// The latest version of the replacement event schema. Version 1 has the ten
+// original columns. Version 2 adds the optional replaced_ancestor_fee,
+// replaced_ancestor_vsize, mempool_min_fee and replacement_peer columns.
+pub const SCHEMA_VERSION: u32 = 2;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RBFEvent {
     pub timestamp: u64,
@@ -32,6 +37,42 @@ pub struct RBFEvent {
         serialize_with = "hex::serde::serialize"
     )]
     pub replacement_raw: Vec<u8>,
+    // Optional columns added in schema version 2.
+    #[serde(default)]
+    pub replaced_ancestor_fee: Option<u64>,
+    #[serde(default)]
+    pub replaced_ancestor_vsize: Option<u64>,
+    // The mempool min fee of the node at the time of the replacement in sat/vByte.
+    #[serde(default)]
+    pub mempool_min_fee: Option<f64>,
+    // The peer that relayed the replacement to the node.
+    #[serde(default)]
+    pub replacement_peer: Option<String>,
+}
+
+// A row that was skipped when reading replacement events in lenient mode.
+#[derive(Debug, Serialize)]
+pub struct RejectedRow {
+    pub path: String,
+    pub row: u64,
+    pub column: String,
+    pub reason: String,
+}
+
+// The txids are stored in the byte order they are serialized in. When
+// displayed, txids are shown in reversed byte order.
+fn txid_to_string(txid: &[u8; TXID_LENGTH]) -> String {
+    hex::encode(txid.iter().rev().cloned().collect::<Vec<u8>>())
+}
+
+impl RBFEvent {
+    pub fn replaced_txid_string(&self) -> String {
+        txid_to_string(&self.replaced_txid)
+    }
+
+    pub fn replacement_txid_string(&self) -> String {
+        txid_to_string(&self.replacement_txid)
+    }
 }
 
 impl fmt::Display for RBFEvent {
@@ -39,24 +80,10 @@ impl fmt::Display for RBFEvent {
         write!(
             f,
             "Transaction({}, fee={}, vsize={}) replaced with Transaction({}, fee={}, vsize={})",
-            hex::encode(
-                &self
-                    .replaced_txid
-                    .iter()
-                    .rev()
-                    .cloned()
-                    .collect::<Vec<u8>>()
-            ),
+            self.replaced_txid_string(),
             self.replaced_fee,
             self.replaced_vsize,
-            hex::encode(
-                &self
-                    .replacement_txid
-                    .iter()
-                    .rev()
-                    .cloned()
-                    .collect::<Vec<u8>>()
-            ),
+            self.replacement_txid_string(),
             self.replacement_fee,
             self.replacement_vsize,
         )
@@ -0,0 +1,267 @@
+use std::str::FromStr;
+
+use rawtx_rs::bitcoin::Txid;
+use serde::Deserialize;
+
+use crate::blocks::MinedLookup;
+use crate::error::Error;
+use crate::html;
+use crate::rbf::ReplacementClass;
+
+// View definitions bundled with the binary. They can be replaced with other
+// definitions with --views.
+static BUNDLED_VIEWS: &str = include_str!("../views.json");
+const BUNDLED_VIEWS_NAME: &str = "<bundled views.json>";
+// Directories of the output that can't be used as view names.
+const RESERVED_NAMES: [&str; 2] = ["tx", "chains"];
+
+// Filters over the fields of a replacement group. A group is part of a view
+// if it matches all filters that are set.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Filters {
+    // Minimum fee delta (sat) between the replacement and the replaced
+    // transactions.
+    min_fee_delta: Option<i64>,
+    // Input types of which the replacement spends at least one, as shown on
+    // the pages, e.g. "P2WPKH".
+    input_types: Option<Vec<String>>,
+    // Output types of which the replacement creates at least one, e.g.
+    // "P2WPKH v0".
+    output_types: Option<Vec<String>>,
+    // If the replacement has an OP_RETURN output.
+    op_return: Option<bool>,
+    // If the replacement was mined. Requires --blocks.
+    mined: Option<bool>,
+    // Minimum number of transactions replaced by the replacement.
+    min_replaced: Option<usize>,
+    // Minimum time (seconds) one of the replaced transactions spent in the
+    // mempool.
+    min_time_in_mempool: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct View {
+    // Rendered into a subdirectory with this name.
+    pub name: String,
+    pub title: String,
+    #[serde(default)]
+    pub filters: Filters,
+}
+
+// Checks if one of the types in strings like "2x P2WPKH", as built by
+// in_and_outputs_to_strings(), is one of the wanted types.
+fn has_type(strings: &[String], types: &[String]) -> bool {
+    strings.iter().any(|s| {
+        let t = s.split_once("x ").map(|(_, t)| t).unwrap_or(s);
+        types.iter().any(|wanted| wanted.eq_ignore_ascii_case(t))
+    })
+}
+
+impl Filters {
+    fn matches_replacement(&self, tx: &html::TransactionContext) -> bool {
+        self.op_return
+            .is_none_or(|op_return| tx.op_return == op_return)
+            && self
+                .input_types
+                .as_ref()
+                .is_none_or(|types| has_type(&tx.inputs, types))
+            && self
+                .output_types
+                .as_ref()
+                .is_none_or(|types| has_type(&tx.outputs, types))
+    }
+
+    // Checks the filters that only depend on the replacement transaction.
+    // Events failing these aren't kept for the view at all, so that the view
+    // still contains the newest matching groups.
+    pub fn matches_event(&self, replacement: &html::ReplacementContext) -> bool {
+        self.matches_replacement(&replacement.replacement)
+    }
+
+    // Views filtering on the mined status can only be filled once the
+    // transactions were looked up in the blocks.
+    pub fn needs_mined(&self) -> bool {
+        self.mined.is_some()
+    }
+
+    // Checks all filters against a complete group. The mined status is taken
+    // from the lookup.
+    pub fn matches(
+        &self,
+        group: &html::ReplacementGroupContext,
+        lookup: Option<&MinedLookup>,
+    ) -> bool {
+        let is_mined = || {
+            Txid::from_str(&group.replacement.txid)
+                .ok()
+                .and_then(|txid| lookup?.tx(&txid))
+                .is_some()
+        };
+        self.matches_replacement(&group.replacement)
+            && self.min_fee_delta.is_none_or(|min| group.delta.fee >= min)
+            && self.mined.is_none_or(|mined| is_mined() == mined)
+            && self
+                .min_replaced
+                .is_none_or(|min| group.replaced.len() >= min)
+            && self
+                .min_time_in_mempool
+                .is_none_or(|min| group.replaced.iter().any(|tx| tx.time_in_mempool >= min))
+    }
+}
+
+impl View {
+    // Loads the view definitions from a JSON file or the bundled ones. Views
+    // filtering on the mined status need the blocks and inputs that can be
+    // read a second time.
+    pub fn load(path: Option<&str>, blocks: bool, rereadable: bool) -> Result<Vec<View>, Error> {
+        let (name, json) = match path {
+            Some(path) => (
+                path,
+                std::fs::read_to_string(path).map_err(|e| Error::Io {
+                    path: path.to_string(),
+                    source: e,
+                })?,
+            ),
+            None => (BUNDLED_VIEWS_NAME, BUNDLED_VIEWS.to_string()),
+        };
+        let views: Vec<View> = serde_json::from_str(&json).map_err(|e| Error::Json {
+            path: name.to_string(),
+            source: e,
+        })?;
+
+        let error = |reason: String| Error::Views {
+            path: name.to_string(),
+            reason,
+        };
+        for (i, view) in views.iter().enumerate() {
+            if view.name.is_empty()
+                || !view
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(error(format!(
+                    "invalid view name '{}': only letters, digits, '_' and '-' are allowed",
+                    view.name
+                )));
+            }
+            if RESERVED_NAMES.contains(&view.name.as_str())
+                || ReplacementClass::ALL.iter().any(|c| c.name() == view.name)
+            {
+                return Err(error(format!("view name '{}' is reserved", view.name)));
+            }
+            if views[..i].iter().any(|v| v.name == view.name) {
+                return Err(error(format!("duplicate view name '{}'", view.name)));
+            }
+            if view.filters.mined.is_some() && !blocks {
+                return Err(error(format!(
+                    "view '{}' filters on the mined status, which requires --blocks",
+                    view.name
+                )));
+            }
+            if view.filters.mined.is_some() && !rereadable {
+                return Err(error(format!(
+                    "view '{}' filters on the mined status, which requires reading the inputs twice and isn't possible with stdin without --store",
+                    view.name
+                )));
+            }
+        }
+        Ok(views)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::NewestReplacementGroups;
+    use crate::testutil::{replacement, txid};
+
+    fn filters(json: &str) -> Filters {
+        serde_json::from_str(json).unwrap()
+    }
+
+    // A group of transactions 1 and 2 replaced by 3 at once.
+    fn group() -> html::ReplacementGroupContext {
+        let mut groups = NewestReplacementGroups::new(ReplacementClass::FullRbf, 1);
+        for replaced in [1, 2] {
+            let mut event = replacement(10, replaced, 3);
+            event.replaced.time_in_mempool = replaced as i64 * 60;
+            groups.insert(&event);
+        }
+        groups.into_groups().remove(0)
+    }
+
+    fn event(edit: impl Fn(&mut html::ReplacementContext)) -> html::ReplacementContext {
+        let mut event = replacement(10, 1, 3);
+        edit(&mut event);
+        event
+    }
+
+    #[test]
+    fn matches_event_on_replacement_fields() {
+        let with_outputs = |e: &mut html::ReplacementContext| {
+            e.replacement.inputs = vec![String::from("2x P2WPKH")];
+            e.replacement.outputs = vec![String::from("1x P2TR v1"), String::from("1x OP_RETURN")];
+            e.replacement.op_return = true;
+        };
+        let event = event(with_outputs);
+        assert!(filters("{}").matches_event(&event));
+        assert!(filters(r#"{"op_return": true}"#).matches_event(&event));
+        assert!(!filters(r#"{"op_return": false}"#).matches_event(&event));
+        assert!(filters(r#"{"input_types": ["p2wpkh"]}"#).matches_event(&event));
+        assert!(!filters(r#"{"input_types": ["P2WPKH v0"]}"#).matches_event(&event));
+        assert!(filters(r#"{"output_types": ["P2WPKH v0", "P2TR v1"]}"#).matches_event(&event));
+        assert!(!filters(r#"{"output_types": ["P2WPKH v0"]}"#).matches_event(&event));
+    }
+
+    #[test]
+    fn matches_group_fields() {
+        let group = group();
+        // 3000 sat for the replacement, 1000 and 2000 sat for the replaced.
+        assert_eq!(group.delta.fee, 0);
+        assert!(filters(r#"{"min_fee_delta": 0}"#).matches(&group, None));
+        assert!(!filters(r#"{"min_fee_delta": 1}"#).matches(&group, None));
+        assert!(filters(r#"{"min_replaced": 2}"#).matches(&group, None));
+        assert!(!filters(r#"{"min_replaced": 3}"#).matches(&group, None));
+        assert!(filters(r#"{"min_time_in_mempool": 120}"#).matches(&group, None));
+        assert!(!filters(r#"{"min_time_in_mempool": 121}"#).matches(&group, None));
+        assert!(!filters(r#"{"min_replaced": 2, "op_return": true}"#).matches(&group, None));
+    }
+
+    #[test]
+    fn matches_mined_status_from_lookup() {
+        let group = group();
+        let mined = filters(r#"{"mined": true}"#);
+        let not_mined = filters(r#"{"mined": false}"#);
+        assert!(mined.needs_mined());
+        assert!(!filters(r#"{"min_replaced": 2}"#).needs_mined());
+
+        let mut lookup = MinedLookup::default();
+        assert!(!mined.matches(&group, Some(&lookup)));
+        assert!(not_mined.matches(&group, Some(&lookup)));
+        assert!(not_mined.matches(&group, None));
+
+        // Only the replacement being mined counts.
+        lookup.insert_tx(
+            txid(1),
+            html::MinedContext {
+                height: 800000,
+                hash: String::new(),
+                pool: None,
+            },
+        );
+        assert!(!mined.matches(&group, Some(&lookup)));
+        lookup.insert_tx(
+            txid(3),
+            html::MinedContext {
+                height: 800001,
+                hash: String::new(),
+                pool: None,
+            },
+        );
+        assert!(mined.matches(&group, Some(&lookup)));
+        assert!(!not_mined.matches(&group, Some(&lookup)));
+    }
+}